@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+// A foreground color set by an ANSI escape sequence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Color {
+    // One of the 256 terminal colors, where 0-7 are the basic colors and 8-15
+    // their bright versions.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+// Strip ANSI escape sequences from s, returning the plain text and the color
+// of each colored character by its (x, y) position in the text.
+pub fn parse(s: &str) -> (String, HashMap<(u16, u16), Color>) {
+    let mut text = String::with_capacity(s.len());
+    let mut colors = HashMap::new();
+    let mut color = None;
+    let (mut x, mut y) = (0u16, 0u16);
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                if chars.next_if_eq(&'[').is_none() {
+                    log::debug!("Dropping escape at {x},{y}");
+                    continue;
+                }
+                // parameters run until a final byte in @..~
+                let mut params = String::new();
+                let mut end = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        end = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if end == Some('m') {
+                    color = sgr(&params, color);
+                } else {
+                    log::debug!("Dropping escape sequence {params:?} {end:?} at {x},{y}");
+                }
+            }
+            // like loading, accept \n, \r\n, and lone \r line endings
+            '\n' | '\r' => {
+                if c == '\r' {
+                    chars.next_if_eq(&'\n');
+                }
+                text.push('\n');
+                (x, y) = (0, y + 1);
+            }
+            c => {
+                text.push(c);
+                if let Some(color) = color {
+                    colors.insert((x, y), color);
+                }
+                x += 1;
+            }
+        }
+    }
+    (text, colors)
+}
+
+// The reverse of parse: text with an escape before each run of characters
+// colored by colors, and a reset at the end of each colored line.
+pub fn write(text: &str, colors: &HashMap<(u16, u16), Color>) -> String {
+    if colors.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    for (y, line) in text.split('\n').enumerate() {
+        if y > 0 {
+            out.push('\n');
+        }
+        let mut current = None;
+        for (x, c) in line.chars().enumerate() {
+            let color = colors.get(&(x as u16, y as u16)).copied();
+            if color != current {
+                out.push_str(&match color {
+                    None => "\x1b[0m".to_string(),
+                    Some(Color::Indexed(i @ 0..=7)) => format!("\x1b[{}m", 30 + i),
+                    Some(Color::Indexed(i @ 8..=15)) => format!("\x1b[{}m", 90 + i - 8),
+                    Some(Color::Indexed(i)) => format!("\x1b[38;5;{i}m"),
+                    Some(Color::Rgb(r, g, b)) => format!("\x1b[38;2;{r};{g};{b}m"),
+                });
+                current = color;
+            }
+            out.push(c);
+        }
+        if current.is_some() {
+            out.push_str("\x1b[0m");
+        }
+    }
+    out
+}
+
+// The foreground color after a Select Graphic Rendition sequence with params.
+// Anything other than foreground color is ignored.
+fn sgr(params: &str, mut color: Option<Color>) -> Option<Color> {
+    let codes: Vec<u8> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        color = match code {
+            0 | 39 => None,
+            30..=37 => Some(Color::Indexed(code - 30)),
+            90..=97 => Some(Color::Indexed(code - 90 + 8)),
+            38 => match codes.next() {
+                Some(5) => codes.next().map(Color::Indexed),
+                Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                    (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+                    _ => color,
+                },
+                _ => color,
+            },
+            _ => color,
+        };
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse() {
+        let (text, colors) = parse("a \x1b[31mred\x1b[0m b\n\x1b[1;38;5;200mx\x1b[39my\x1b[2K");
+        assert_eq!(text, "a red b\nxy");
+        let mut colors: Vec<_> = colors.into_iter().collect();
+        colors.sort();
+        assert_eq!(
+            colors,
+            vec![
+                ((0, 1), Color::Indexed(200)),
+                ((2, 0), Color::Indexed(1)),
+                ((3, 0), Color::Indexed(1)),
+                ((4, 0), Color::Indexed(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_line_endings() {
+        let (text, colors) = parse("a\r\n\x1b[31mb\rc\x1b[31md");
+        assert_eq!(text, "a\nb\ncd");
+        assert_eq!(colors[&(0, 1)], Color::Indexed(1));
+        assert_eq!(colors[&(1, 2)], Color::Indexed(1));
+        assert_eq!(colors.len(), 3);
+    }
+
+    #[test]
+    fn test_write() {
+        let s =
+            "a \x1b[31mred\x1b[0m b\n\x1b[38;5;200mx\x1b[0my\n\x1b[92mz\x1b[38;2;1;2;3mw\x1b[0m";
+        let (text, colors) = parse(s);
+        assert_eq!(write(&text, &colors), s);
+        assert_eq!(write(&text, &HashMap::new()), text);
+    }
+
+    #[test]
+    fn test_parse_rgb() {
+        let (text, colors) = parse("\x1b[38;2;1;2;3mab\x1b[m\x1b[92mc");
+        assert_eq!(text, "abc");
+        assert_eq!(colors[&(0, 0)], Color::Rgb(1, 2, 3));
+        assert_eq!(colors[&(1, 0)], Color::Rgb(1, 2, 3));
+        assert_eq!(colors[&(2, 0)], Color::Indexed(10));
+    }
+}
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
 
-use crate::config::{self, Binding};
+use crate::config::{self, Action, Binding};
 
 #[derive(Default, Debug)]
 pub struct Binds(HashMap<KeyEvent, Binding>);
@@ -27,11 +27,17 @@ fn map_key(key: &str) -> Result<KeyEvent> {
     let Some(code) = parts.next() else {
         bail!("Empty key");
     };
-    let code = match code {
-        c if c.len() == 1 => KeyCode::Char(c.chars().next().unwrap()),
-        s if s.starts_with("f") => {
+    // letters are case sensitive, but key names are not
+    let code = match code.to_ascii_lowercase().as_str() {
+        _ if code.len() == 1 => KeyCode::Char(code.chars().next().unwrap()),
+        s if s.starts_with('f') => {
             let (_, num) = s.split_at(1);
-            let num = num.parse()?;
+            let num: u8 = num
+                .parse()
+                .with_context(|| format!("Unknown key: {code}"))?;
+            if !(1..=12).contains(&num) {
+                bail!("Function key out of range: {code}");
+            }
             KeyCode::F(num)
         }
         "backspace" => KeyCode::Backspace,
@@ -57,7 +63,7 @@ fn map_key(key: &str) -> Result<KeyEvent> {
         "pause" => KeyCode::Pause,
         "menu" => KeyCode::Menu,
         "keypadbegin" => KeyCode::KeypadBegin,
-        unknown => bail!("Unknown key: {unknown}"),
+        _ => bail!("Unknown key: {code}"),
     };
     let mut modifiers = KeyModifiers::empty();
     for p in parts {
@@ -78,9 +84,51 @@ fn map_key(key: &str) -> Result<KeyEvent> {
 
 impl Binds {}
 
+// Problems found in a set of bindings by `boxt config check`.
+#[derive(Debug, Default)]
+pub struct Report {
+    // Each key that is bound more than once, with every name it was given.
+    pub conflicts: Vec<Vec<String>>,
+    // Actions that no key triggers.
+    pub unbound: Vec<Action>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+// Check bindings as (key, binding) pairs, before they are collapsed into a
+// map, so the same key given twice or spelled two ways (e.g. "M" and "S-m")
+// is caught.
+pub fn check<'a>(binds: impl IntoIterator<Item = (&'a str, &'a Binding)>) -> Result<Report> {
+    let mut keys: HashMap<KeyEvent, Vec<String>> = HashMap::new();
+    let mut bound = vec![];
+    for (k, v) in binds {
+        keys.entry(map_key(k)?).or_default().push(k.to_string());
+        match v {
+            Binding::Single(a) => bound.push(a.clone()),
+            Binding::Multi(m) => bound.extend(m.iter().cloned()),
+        }
+    }
+
+    let mut conflicts: Vec<_> = keys.into_values().filter(|v| v.len() > 1).collect();
+    conflicts.iter_mut().for_each(|v| v.sort());
+    conflicts.sort();
+
+    let unbound = Action::ALL
+        .iter()
+        .filter(|a| !bound.contains(a))
+        .cloned()
+        .collect();
+
+    Ok(Report { conflicts, unbound })
+}
+
 #[cfg(test)]
 mod tests {
-    use config::{Action, BindConfig, Binding};
+    use config::{BindConfig, Binding};
 
     use super::*;
 
@@ -162,4 +210,100 @@ mod tests {
             Some(&alt_enter)
         );
     }
+
+    #[test]
+    fn test_map_key_function() {
+        assert_eq!(
+            map_key("F5").unwrap(),
+            KeyEvent::new(KeyCode::F(5), KeyModifiers::empty())
+        );
+        assert_eq!(
+            map_key("f12").unwrap(),
+            KeyEvent::new(KeyCode::F(12), KeyModifiers::empty())
+        );
+        assert_eq!(
+            map_key("C-f1").unwrap(),
+            KeyEvent::new(KeyCode::F(1), KeyModifiers::CONTROL)
+        );
+        // a lone f is just a letter
+        assert_eq!(
+            map_key("f").unwrap(),
+            KeyEvent::new(KeyCode::Char('f'), KeyModifiers::empty())
+        );
+        assert!(map_key("f99").is_err());
+        assert!(map_key("f0").is_err());
+        assert!(map_key("foo").is_err());
+    }
+
+    #[test]
+    fn test_map_key_named_case() {
+        for (name, code) in [
+            ("Enter", KeyCode::Enter),
+            ("ESC", KeyCode::Esc),
+            ("Tab", KeyCode::Tab),
+            ("PageDown", KeyCode::PageDown),
+        ] {
+            assert_eq!(
+                map_key(name).unwrap(),
+                KeyEvent::new(code, KeyModifiers::empty()),
+                "{name}"
+            );
+        }
+        assert_eq!(
+            map_key("C-Enter").unwrap(),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL)
+        );
+        // single letters keep their case
+        assert_eq!(
+            map_key("E").unwrap(),
+            KeyEvent::new(KeyCode::Char('E'), KeyModifiers::empty())
+        );
+        assert!(map_key("Entr").is_err());
+    }
+
+    #[test]
+    fn test_check_clean() {
+        // the defaults as listed, so a key given twice is not hidden
+        let c = BindConfig::default();
+        let r = check(c.0.iter().map(|(k, v)| (k.as_str(), v))).unwrap();
+        assert!(r.is_ok());
+        assert_eq!(r.conflicts, Vec::<Vec<String>>::new());
+        // left for users to bind, as they have a default elsewhere or are rarely
+        // wanted
+        assert_eq!(
+            r.unbound,
+            vec![
+                Action::JumpUp,
+                Action::JumpDown,
+                Action::JumpLeft,
+                Action::JumpRight,
+                Action::DrawDimension,
+                Action::ExitMode,
+                Action::TextAddLine,
+                Action::UndoAll,
+                Action::RedoAll,
+                Action::ToggleBorder,
+                Action::ToggleMinimap,
+                Action::ToggleWhitespace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_conflict() {
+        let mirror = Binding::Single(Action::LineMirror);
+        let select = Binding::Single(Action::SelectRect);
+        let mark = Binding::Single(Action::SetMark);
+        let r = check([("m", &mirror), ("M", &mark), ("m", &select), ("S-m", &mark)]).unwrap();
+        assert!(!r.is_ok());
+        assert_eq!(
+            r.conflicts,
+            vec![
+                vec!["M".to_string(), "S-m".to_string()],
+                vec!["m".to_string(), "m".to_string()],
+            ]
+        );
+        assert!(!r.unbound.contains(&Action::LineMirror));
+        assert!(r.unbound.contains(&Action::Quit));
+    }
 }
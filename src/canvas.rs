@@ -1,12 +1,98 @@
-use crate::{edit::Edit, line::Line, rect::Rect, vec::UVec};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ansi::{self, Color},
+    edit::Edit,
+    line::{Line, LineGlyphs},
+    rect::{Rect, RectGlyphs},
+    text::Text,
+    theme::Theme,
+    vec::{IVec, UVec},
+};
 
 const EMPTY: char = ' ';
+// Written in place of control characters, which would break up the grid.
+const PLACEHOLDER: char = '\u{FFFD}';
 
-#[derive(Default, Debug, Clone)]
+// Each character that looks different turned a quarter turn clockwise, and
+// what it turns into.
+const ROTATIONS: &[(char, char)] = &[
+    ('-', '|'),
+    ('|', '-'),
+    ('/', '\\'),
+    ('\\', '/'),
+    ('^', '>'),
+    ('>', 'v'),
+    ('v', '<'),
+    ('<', '^'),
+    ('─', '│'),
+    ('│', '─'),
+    ('═', '║'),
+    ('║', '═'),
+    ('┄', '┆'),
+    ('┆', '┄'),
+    ('┌', '┐'),
+    ('┐', '┘'),
+    ('┘', '└'),
+    ('└', '┌'),
+    ('╭', '╮'),
+    ('╮', '╯'),
+    ('╯', '╰'),
+    ('╰', '╭'),
+    ('╔', '╗'),
+    ('╗', '╝'),
+    ('╝', '╚'),
+    ('╚', '╔'),
+    ('├', '┬'),
+    ('┬', '┤'),
+    ('┤', '┴'),
+    ('┴', '├'),
+    ('▲', '▶'),
+    ('▶', '▼'),
+    ('▼', '◀'),
+    ('◀', '▲'),
+];
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
 struct UndoRedo {
     edits: Vec<Edit>,
     size_x: usize,
     size_y: usize,
+    // Colors the edits drew over, to put back with the characters.
+    #[serde(default)]
+    colors: Vec<((u16, u16), Color)>,
+}
+
+impl UndoRedo {
+    fn overlaps(&self, other: &UndoRedo) -> bool {
+        self.edits
+            .iter()
+            .flat_map(|e| e.cells())
+            .any(|p| other.edits.iter().any(|e| e.contains(p)))
+    }
+}
+
+// The undo and redo stacks of a canvas, kept to be restored onto the same
+// content later.
+#[derive(Default, Debug, Deserialize, Serialize)]
+pub struct History {
+    content: String,
+    undo: Vec<UndoRedo>,
+    redo: Vec<UndoRedo>,
+}
+
+// The JSON representation of a canvas.
+#[derive(Serialize, Deserialize)]
+struct CellGrid {
+    width: usize,
+    height: usize,
+    rows: Vec<Vec<char>>,
 }
 
 #[derive(Default, Clone)]
@@ -14,6 +100,11 @@ pub struct Canvas {
     current: Vec<Vec<char>>,
     undo: Vec<UndoRedo>,
     redo: Vec<UndoRedo>,
+    // Overlapping edits made within this long of each other are undone as one.
+    merge_window: Duration,
+    last_edit: Option<Instant>,
+    // Foreground colors of cells loaded with ANSI escapes, by (x, y).
+    colors: HashMap<(u16, u16), Color>,
 }
 
 impl Canvas {
@@ -40,8 +131,19 @@ impl Canvas {
         self.current[point.y as usize][point.x as usize]
     }
 
+    // The color of a cell loaded with ANSI escapes, until it is drawn over.
+    pub fn color(&self, point: UVec) -> Option<Color> {
+        self.colors.get(&(point.x, point.y)).copied()
+    }
+
+    pub fn set_colors(&mut self, colors: HashMap<(u16, u16), Color>) {
+        self.colors = colors;
+    }
+
     pub fn from_str(s: &str) -> Canvas {
-        let w = s.lines().map(|l| l.len()).sum();
+        // accept \n, \r\n, and lone \r line endings
+        let s = s.replace("\r\n", "\n").replace('\r', "\n");
+        let w = s.lines().map(|l| l.chars().count()).max().unwrap_or(0);
         let current = s
             .lines()
             .map(|l| {
@@ -56,27 +158,96 @@ impl Canvas {
         }
     }
 
+    // Crop blank columns at the right and blank rows at the bottom, as part of
+    // loading rather than as an edit that can be undone.
+    pub fn trim(&mut self) {
+        let used = self.used();
+        self.resize_y(used.y as usize, used.x as usize);
+        self.resize_x(used.x as usize);
+    }
+
+    // Turn rows of cells a quarter turn clockwise, turning each character with
+    // them where it has a rotated counterpart.
+    pub fn rotate(rows: &[Vec<char>]) -> Vec<Vec<char>> {
+        let width = rows.first().map_or(0, |r| r.len());
+        (0..width)
+            .map(|x| {
+                rows.iter()
+                    .rev()
+                    .map(|row| row.get(x).copied().unwrap_or(EMPTY))
+                    .map(|c| {
+                        ROTATIONS
+                            .iter()
+                            .find(|(from, _)| *from == c)
+                            .map_or(c, |(_, to)| *to)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // The number and length of each line in s longer than max characters.
+    pub fn long_lines(s: &str, max: usize) -> Vec<(usize, usize)> {
+        s.lines()
+            .map(|l| l.chars().count())
+            .enumerate()
+            .filter(|(_, len)| *len > max)
+            .map(|(i, len)| (i + 1, len))
+            .collect()
+    }
+
+    pub fn from_json(s: &str) -> Result<Canvas> {
+        let grid: CellGrid = serde_json::from_str(s)?;
+        if grid.rows.len() != grid.height {
+            bail!("Expected {} rows, got {}", grid.height, grid.rows.len());
+        }
+        if let Some(row) = grid.rows.iter().find(|r| r.len() != grid.width) {
+            bail!("Expected rows of width {}, got {}", grid.width, row.len());
+        }
+        Ok(Self {
+            current: grid.rows,
+            ..Default::default()
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let (height, width) = self.size();
+        Ok(serde_json::to_string(&CellGrid {
+            width,
+            height,
+            rows: self.current.clone(),
+        })?)
+    }
+
     // Returns the list of edits to undo this edit.
-    fn apply_edits(&mut self, edits: impl Iterator<Item = Edit>, expand: bool) -> UndoRedo {
+    // Fresh edits (as opposed to an undo or redo) may expand the canvas, and
+    // joined edits turn the lines they cross into junctions.
+    fn apply_edits(
+        &mut self,
+        edits: impl Iterator<Item = Edit>,
+        fresh: bool,
+        join: bool,
+    ) -> UndoRedo {
         let (size_y, size_x) = self.size();
         let mut undo = vec![];
+        let mut colors = vec![];
         for e in edits {
             log::trace!("Applying edit: {e:?}");
             let mut old = vec![];
-            if expand {
+            if fresh {
                 self.maybe_expand(e.bounds());
             }
             match e {
                 Edit::Right { start, chars } => {
                     for (i, c) in chars.iter().enumerate() {
-                        let c = self.put(start.x + i as u16, start.y, *c);
+                        let c = self.put(start.x + i as u16, start.y, *c, join, &mut colors);
                         old.push(c);
                     }
                     undo.push(Edit::Right { start, chars: old });
                 }
                 Edit::Down { start, chars } => {
                     for (i, c) in chars.iter().enumerate() {
-                        let c = self.put(start.x, start.y + i as u16, *c);
+                        let c = self.put(start.x, start.y + i as u16, *c, join, &mut colors);
                         old.push(c);
                     }
                     undo.push(Edit::Down { start, chars: old });
@@ -90,14 +261,123 @@ impl Canvas {
             edits: undo,
             size_x,
             size_y,
+            colors,
         }
     }
 
-    pub fn edit(&mut self, edits: impl Iterator<Item = Edit>) {
-        let undo = self.apply_edits(edits, true);
-        log::debug!("Pushing undo: {undo:?}");
-        self.undo.push(undo);
+    pub fn set_merge_window(&mut self, window: Duration) {
+        self.merge_window = window;
+    }
+
+    // Whether applying undo would leave the canvas as it is.
+    fn is_noop(&self, undo: &UndoRedo) -> bool {
+        (undo.size_y, undo.size_x) == self.size()
+            && undo.colors.is_empty()
+            && undo
+                .edits
+                .iter()
+                .flat_map(|e| e.cells().zip(e.chars()))
+                .all(|(p, c)| self.get(p) == c)
+    }
+
+    // Returns true if a new frame was pushed to the undo stack, or false if the
+    // edit changed nothing or was merged into the previous frame.
+    pub fn edit(&mut self, edits: impl Iterator<Item = Edit>) -> bool {
+        self.push_edits(edits, false)
+    }
+
+    // Like edit, but for lines and rects, which join the lines they cross.
+    pub fn draw(&mut self, edits: impl Iterator<Item = Edit>) -> bool {
+        self.push_edits(edits, true)
+    }
+
+    fn push_edits(&mut self, edits: impl Iterator<Item = Edit>, join: bool) -> bool {
+        let mut undo = self.apply_edits(edits, true, join);
         self.redo.clear();
+        if self.is_noop(&undo) {
+            log::debug!("Dropping undo that changes nothing: {undo:?}");
+            return false;
+        }
+
+        let now = Instant::now();
+        let recent = self
+            .last_edit
+            .is_some_and(|t| now.duration_since(t) < self.merge_window);
+        self.last_edit = Some(now);
+        match self.undo.last_mut() {
+            Some(prev) if recent && undo.overlaps(prev) => {
+                log::debug!("Merging undo: {undo:?}");
+                // undo the newer edit first, from the size before the older
+                undo.edits.append(&mut prev.edits);
+                prev.edits = undo.edits;
+                prev.colors.append(&mut undo.colors);
+                false
+            }
+            _ => {
+                log::debug!("Pushing undo: {undo:?}");
+                self.undo.push(undo);
+                true
+            }
+        }
+    }
+
+    // Apply an undo or redo frame, returning the frame that reverses it.
+    fn restore(&mut self, frame: UndoRedo) -> UndoRedo {
+        let (size_y, size_x) = self.size();
+
+        // grow first so the edits are in bounds, then shrink after
+        self.maybe_expand(UVec {
+            x: frame.size_x as u16,
+            y: frame.size_y as u16,
+        });
+        let mut inverse = self.apply_edits(frame.edits.into_iter(), false, false);
+        self.colors.extend(frame.colors);
+
+        // the canvas may have been resized since the frame was made, so never
+        // shrink past content the frame does not restore
+        let used = self.used();
+        let (new_y, new_x) = (
+            frame.size_y.max(used.y as usize),
+            frame.size_x.max(used.x as usize),
+        );
+        if (new_y, new_x) != (frame.size_y, frame.size_x) {
+            log::warn!(
+                "Keeping size {new_x}x{new_y} over {}x{} to not crop content",
+                frame.size_x,
+                frame.size_y
+            );
+        }
+        self.resize_y(new_y, new_x);
+        self.resize_x(new_x);
+
+        inverse.size_x = size_x;
+        inverse.size_y = size_y;
+        inverse
+    }
+
+    pub fn history(&self) -> History {
+        History {
+            content: self.to_string(),
+            undo: self.undo.clone(),
+            redo: self.redo.clone(),
+        }
+    }
+
+    // Replace the undo and redo stacks with those from history. Returns false,
+    // leaving them as they were, if the history was taken from other content.
+    pub fn restore_history(&mut self, history: History) -> bool {
+        if history.content != self.to_string() {
+            log::warn!("Ignoring undo history saved from different content");
+            return false;
+        }
+        log::debug!(
+            "Restoring {} undo and {} redo frames",
+            history.undo.len(),
+            history.redo.len()
+        );
+        self.undo = history.undo;
+        self.redo = history.redo;
+        true
     }
 
     pub fn undo(&mut self) {
@@ -107,15 +387,10 @@ impl Canvas {
         };
 
         log::debug!("Performing undo: {undo:?}");
-        let redo = self.apply_edits(undo.edits.into_iter(), false);
+        let redo = self.restore(undo);
 
         log::debug!("Pushing redo: {redo:?}");
         self.redo.push(redo);
-
-        // resize after, as an undo will typically shrink the canvas
-        // if we shrink first, our edits will be out of bounds
-        self.resize_y(undo.size_y, undo.size_x);
-        self.resize_x(undo.size_x);
     }
 
     pub fn redo(&mut self) {
@@ -125,30 +400,435 @@ impl Canvas {
         };
 
         log::debug!("Performing redo: {redo:?}");
-
-        // resize after, as an redo will typically expand the canvas
-        // we need the canvas large enough to accomodate our edits
-        self.resize_y(redo.size_y, redo.size_x);
-        self.resize_x(redo.size_x);
-        let undo = self.apply_edits(redo.edits.into_iter(), false);
+        let undo = self.restore(redo);
 
         log::debug!("Pushing undo: {undo:?}");
         self.undo.push(undo);
     }
 
-    pub fn clear(&mut self, point: UVec) {
+    // Undo until there is nothing left to undo.
+    pub fn undo_all(&mut self) {
+        log::debug!("Undoing {} edits", self.undo.len());
+        while let Some(undo) = self.undo.pop() {
+            let redo = self.restore(undo);
+            self.redo.push(redo);
+        }
+    }
+
+    // Redo until there is nothing left to redo.
+    pub fn redo_all(&mut self) {
+        log::debug!("Redoing {} edits", self.redo.len());
+        while let Some(redo) = self.redo.pop() {
+            let undo = self.restore(redo);
+            self.undo.push(undo);
+        }
+    }
+
+    // Insert d.x empty columns at the left and d.y empty rows at the top,
+    // moving the content and undo history down and right. This is not itself
+    // undoable.
+    pub fn shift(&mut self, d: UVec) {
+        log::debug!("Shifting canvas by {d:?}");
+        let (_, size_x) = self.size();
+        let width = size_x + d.x as usize;
+        for row in &mut self.current {
+            row.splice(0..0, std::iter::repeat(EMPTY).take(d.x as usize));
+        }
+        self.current.splice(
+            0..0,
+            std::iter::repeat(vec![EMPTY; width]).take(d.y as usize),
+        );
+
+        let offset = IVec {
+            x: d.x as i16,
+            y: d.y as i16,
+        };
+        for frame in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            frame.edits = frame.edits.iter().map(|e| e.translated(offset)).collect();
+            frame.size_x += d.x as usize;
+            frame.size_y += d.y as usize;
+            for ((x, y), _) in &mut frame.colors {
+                (*x, *y) = (*x + d.x, *y + d.y);
+            }
+        }
+        self.colors = std::mem::take(&mut self.colors)
+            .into_iter()
+            .map(|((x, y), c)| ((x + d.x, y + d.y), c))
+            .collect();
+    }
+
+    // Move all content one step in direction d as a single edit, growing the
+    // canvas to the right or bottom to make room. Content is never moved off
+    // the top or left. Returns false if nothing moved.
+    pub fn translate(&mut self, d: IVec) -> bool {
+        let (size_y, size_x) = self.size();
+        let filled = |c: &char| *c != EMPTY;
+        let edge = (d.y < 0 && self.current.first().is_some_and(|r| r.iter().any(filled)))
+            || (d.x < 0 && self.current.iter().any(|r| r.first().is_some_and(filled)));
+        if edge {
+            log::info!("Not shifting content past the edge: {d:?}");
+            return false;
+        }
+
+        // clear first, so moved lines don't join with what was there
+        let clear = (0..size_y).map(|y| Edit::Right {
+            start: UVec { x: 0, y: y as u16 },
+            chars: vec![EMPTY; size_x],
+        });
+        let moved: Vec<_> = self
+            .current
+            .iter()
+            .enumerate()
+            .filter_map(|(y, row)| {
+                let y = u16::try_from(y as i16 + d.y).ok()?;
+                let (x, chars) = match d.x {
+                    1 => (1, row.clone()),
+                    -1 => (0, row.get(1..)?.to_vec()),
+                    _ => (0, row.clone()),
+                };
+                Some(Edit::Right {
+                    start: UVec { x, y },
+                    chars,
+                })
+            })
+            .collect();
+        log::debug!("Translating canvas by {d:?}");
+        self.edit(clear.chain(moved))
+    }
+
+    // Replace the canvas with a blank one of size, as a single undoable edit.
+    pub fn reset(&mut self, size: UVec) {
+        let (size_y, size_x) = self.size();
+        let edits = self
+            .current
+            .iter()
+            .enumerate()
+            .map(|(y, row)| Edit::Right {
+                start: UVec { x: 0, y: y as u16 },
+                chars: row.clone(),
+            })
+            .collect();
+        self.undo.push(UndoRedo {
+            edits,
+            size_x,
+            size_y,
+            colors: std::mem::take(&mut self.colors).into_iter().collect(),
+        });
+        self.redo.clear();
+
+        log::debug!("Resetting to {size:?}");
+        self.current = vec![vec![EMPTY; size.x as usize]; size.y as usize];
+    }
+
+    // Set the canvas size, filling new cells with EMPTY or cropping cells
+    // outside of it. Returns false if the size did not change.
+    pub fn resize(&mut self, size: UVec) -> bool {
+        let (size_y, size_x) = self.size();
+        let (w, h) = (size.x as usize, size.y as usize);
+        if (h, w) == (size_y, size_x) {
+            return false;
+        }
+
+        // undo restores any cells that are cropped
+        let edits = (0..size_y)
+            .filter_map(|y| {
+                let x = if y < h { w } else { 0 };
+                (x < size_x).then(|| Edit::Right {
+                    start: UVec {
+                        x: x as u16,
+                        y: y as u16,
+                    },
+                    chars: self.current[y][x..].to_vec(),
+                })
+            })
+            .collect();
+        let inside = |(x, y): &(u16, u16)| (*x as usize) < w && (*y as usize) < h;
+        let colors = self
+            .colors
+            .iter()
+            .filter(|(p, _)| !inside(p))
+            .map(|(p, c)| (*p, *c))
+            .collect();
+        self.colors.retain(|p, _| inside(p));
+        let undo = UndoRedo {
+            edits,
+            size_x,
+            size_y,
+            colors,
+        };
+
+        log::debug!("Resizing to {size:?}");
+        self.resize_y(h, w);
+        self.resize_x(w);
+        self.undo.push(undo);
+        self.redo.clear();
+        true
+    }
+
+    // Replace the cell at point with empty.
+    pub fn clear(&mut self, point: UVec, empty: char) -> bool {
         self.edit(std::iter::once(Edit::Right {
             start: point,
-            chars: vec![EMPTY],
-        }));
+            chars: vec![empty],
+        }))
+    }
+
+    // Clear line and box characters within the bounds of rect, leaving other
+    // content, such as text labels, intact.
+    pub fn erase_lines(&mut self, rect: &Rect, empty: char) -> bool {
+        let (size_y, size_x) = self.size();
+        let (tl, br) = (rect.top_left, rect.bottom_right);
+        let (x1, x2) = (std::cmp::min(tl.x, br.x), std::cmp::max(tl.x, br.x));
+        let (y1, y2) = (std::cmp::min(tl.y, br.y), std::cmp::max(tl.y, br.y));
+        let edits: Vec<_> = (y1..=y2)
+            .flat_map(|y| (x1..=x2).map(move |x| UVec { x, y }))
+            .filter(|p| (p.x as usize) < size_x && (p.y as usize) < size_y)
+            .filter(|p| Line::is_line_char(self.get(*p)))
+            .map(|p| Edit::Right {
+                start: p,
+                chars: vec![empty],
+            })
+            .collect();
+        log::debug!("Erasing {} line chars in {rect:?}", edits.len());
+        self.edit(edits.into_iter())
+    }
+
+    // The cells within the bounds of rect, cropped to the canvas.
+    pub fn region(&self, rect: &Rect) -> Vec<Vec<char>> {
+        let (tl, br) = (rect.top_left, rect.bottom_right);
+        let (x1, x2) = (std::cmp::min(tl.x, br.x), std::cmp::max(tl.x, br.x));
+        let (y1, y2) = (std::cmp::min(tl.y, br.y), std::cmp::max(tl.y, br.y));
+        self.current
+            .iter()
+            .skip(y1 as usize)
+            .take((y2 - y1) as usize + 1)
+            .map(|row| {
+                row.iter()
+                    .skip(x1 as usize)
+                    .take((x2 - x1) as usize + 1)
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Fill every cell within the bounds of rect with empty.
+    pub fn erase_region(&mut self, rect: &Rect, empty: char) -> bool {
+        let (size_y, size_x) = self.size();
+        let (tl, br) = (rect.top_left, rect.bottom_right);
+        let (x1, x2) = (std::cmp::min(tl.x, br.x), std::cmp::max(tl.x, br.x));
+        let (y1, y2) = (std::cmp::min(tl.y, br.y), std::cmp::max(tl.y, br.y));
+        let x2 = std::cmp::min(x2 as usize + 1, size_x) as u16;
+        let edits: Vec<_> = (y1..=y2)
+            .filter(|y| (*y as usize) < size_y && x1 < x2)
+            .map(|y| Edit::Right {
+                start: UVec { x: x1, y },
+                chars: vec![empty; (x2 - x1) as usize],
+            })
+            .collect();
+        log::debug!("Erasing {} rows in {rect:?}", edits.len());
+        self.edit(edits.into_iter())
+    }
+
+    // Fill gaps in the borders of boxes drawn with any of styles, as one
+    // undoable edit. A box is found from its four corners, with its sides drawn
+    // in line characters but for at most one missing cell.
+    pub fn repair_boxes(&mut self, styles: &[RectGlyphs]) -> bool {
+        let (size_y, size_x) = self.size();
+        let mut edits = vec![];
+        for y in 0..size_y as u16 {
+            for x in 0..size_x as u16 {
+                let top_left = UVec { x, y };
+                for g in styles.iter().filter(|g| self.get(top_left) == g.top_left) {
+                    edits.extend(self.box_gap(top_left, g));
+                }
+            }
+        }
+        log::debug!("Repairing {} gaps in box borders", edits.len());
+        self.edit(edits.into_iter())
+    }
+
+    // Every box with an unbroken border drawn in g.
+    fn boxes(&self, g: &RectGlyphs) -> Vec<Rect> {
+        let (size_y, size_x) = self.size();
+        let corner = |start: UVec, d: IVec, c: char| {
+            self.border(start, d)
+                .into_iter()
+                .filter(move |(p, gaps)| gaps.is_empty() && self.get(*p) == c)
+                .map(|(p, _)| p)
+        };
+        let mut boxes = vec![];
+        for y in 0..size_y as u16 {
+            for x in 0..size_x as u16 {
+                let top_left = UVec { x, y };
+                if self.get(top_left) != g.top_left {
+                    continue;
+                }
+                let found = corner(top_left, IVec::RIGHT, g.top_right)
+                    .flat_map(|tr| {
+                        corner(top_left, IVec::DOWN, g.bottom_left).map(move |bl| (tr, bl))
+                    })
+                    .find(|(tr, bl)| {
+                        let br = UVec { x: tr.x, y: bl.y };
+                        corner(*bl, IVec::RIGHT, g.bottom_right).any(|p| p == br)
+                            && corner(*tr, IVec::DOWN, g.bottom_right).any(|p| p == br)
+                    });
+                if let Some((tr, bl)) = found {
+                    boxes.push(Rect {
+                        glyphs: *g,
+                        ..Rect::new(x, y, tr.x, bl.y)
+                    });
+                }
+            }
+        }
+        boxes
+    }
+
+    // Redraw boxes, lines, and arrowheads drawn in the glyphs of one theme with
+    // those of another, as one undoable edit. Line glyphs are only changed
+    // beside other line characters, so the same characters in text are kept.
+    pub fn restyle(&mut self, from: &Theme, to: &Theme) -> bool {
+        let (size_y, size_x) = self.size();
+        let mut corners = HashMap::new();
+        for b in self.boxes(&from.rect) {
+            let (tl, br) = (b.top_left, b.bottom_right);
+            corners.insert((tl.x, tl.y), to.rect.top_left);
+            corners.insert((br.x, tl.y), to.rect.top_right);
+            corners.insert((tl.x, br.y), to.rect.bottom_left);
+            corners.insert((br.x, br.y), to.rect.bottom_right);
+        }
+        let glyphs = [
+            (from.rect.horizontal, to.rect.horizontal),
+            (from.rect.vertical, to.rect.vertical),
+            (from.line.horizontal, to.line.horizontal),
+            (from.line.vertical, to.line.vertical),
+            (from.line.corner, to.line.corner),
+            (from.arrow.up, to.arrow.up),
+            (from.arrow.down, to.arrow.down),
+            (from.arrow.left, to.arrow.left),
+            (from.arrow.right, to.arrow.right),
+        ];
+        let beside_line = |p: UVec| {
+            [IVec::UP, IVec::DOWN, IVec::LEFT, IVec::RIGHT]
+                .into_iter()
+                .map(|d| p + d)
+                .filter(|n| *n != p && (n.x as usize) < size_x && (n.y as usize) < size_y)
+                .any(|n| Line::is_line_char(self.get(n)))
+        };
+
+        let mut edits = vec![];
+        for y in 0..size_y as u16 {
+            for x in 0..size_x as u16 {
+                let p = UVec { x, y };
+                let c = self.get(p);
+                let to = corners.get(&(x, y)).copied().or_else(|| {
+                    glyphs
+                        .iter()
+                        .find(|(from, _)| *from == c)
+                        .filter(|_| beside_line(p))
+                        .map(|(_, to)| *to)
+                });
+                if let Some(to) = to.filter(|to| *to != c) {
+                    edits.push(Edit::Right {
+                        start: p,
+                        chars: vec![to],
+                    });
+                }
+            }
+        }
+        log::debug!(
+            "Restyling {} cells, {} box corners",
+            edits.len(),
+            corners.len()
+        );
+        self.edit(edits.into_iter())
+    }
+
+    // The edit filling the gap in the border of a box at top_left, if any.
+    fn box_gap(&self, top_left: UVec, g: &RectGlyphs) -> Option<Edit> {
+        let top = self.border(top_left, IVec::RIGHT);
+        let left = self.border(top_left, IVec::DOWN);
+        for (top_right, top_gaps) in top.iter().filter(|(p, _)| self.get(*p) == g.top_right) {
+            for (bottom_left, left_gaps) in
+                left.iter().filter(|(p, _)| self.get(*p) == g.bottom_left)
+            {
+                let bottom_right = UVec {
+                    x: top_right.x,
+                    y: bottom_left.y,
+                };
+                if self.get(bottom_right) != g.bottom_right {
+                    continue;
+                }
+                let reaches = |start, d| {
+                    self.border(start, d)
+                        .into_iter()
+                        .find(|(p, _)| *p == bottom_right)
+                        .map(|(_, gaps)| gaps)
+                };
+                let (Some(bottom_gaps), Some(right_gaps)) = (
+                    reaches(*bottom_left, IVec::RIGHT),
+                    reaches(*top_right, IVec::DOWN),
+                ) else {
+                    continue;
+                };
+                let horizontal = top_gaps.iter().chain(&bottom_gaps);
+                let vertical = left_gaps.iter().chain(&right_gaps);
+                let gaps: Vec<_> = horizontal
+                    .map(|p| (*p, g.horizontal))
+                    .chain(vertical.map(|p| (*p, g.vertical)))
+                    .collect();
+                match gaps[..] {
+                    [] => return None,
+                    [(start, c)] => {
+                        log::debug!("Filling gap at {start:?} in box at {top_left:?}");
+                        return Some(Edit::Right {
+                            start,
+                            chars: vec![c],
+                        });
+                    }
+                    _ => log::trace!("Too many gaps for a box at {top_left:?}: {gaps:?}"),
+                }
+            }
+        }
+        None
     }
 
-    fn find(&self, mut point: UVec, dx: i16, dy: i16, c: &[char]) -> Option<UVec> {
+    // Walk right or down from start over line characters, stopping at a
+    // second gap. Returns each drawn cell passed, with the gaps before it.
+    fn border(&self, start: UVec, d: IVec) -> Vec<(UVec, Vec<UVec>)> {
         let (size_y, size_x) = self.size();
-        while point.x < size_x as u16 && point.y < size_y as u16 {
+        let mut cells = vec![];
+        let mut gaps = vec![];
+        let mut p = start + d;
+        while (p.x as usize) < size_x && (p.y as usize) < size_y {
+            match self.get(p) {
+                EMPTY if gaps.is_empty() => gaps.push(p),
+                EMPTY => break,
+                c if Line::is_line_char(c) => cells.push((p, gaps.clone())),
+                _ => break,
+            }
+            p = p + d;
+        }
+        cells
+    }
+
+    // Find the nearest of c at most max_distance cells from point.
+    fn find(
+        &self,
+        mut point: UVec,
+        dx: i16,
+        dy: i16,
+        c: &[char],
+        max_distance: u16,
+    ) -> Option<UVec> {
+        let (size_y, size_x) = self.size();
+        let mut distance = 0u32;
+        while point.x < size_x as u16 && point.y < size_y as u16 && distance <= max_distance as u32
+        {
             if c.contains(&self.get(point)) {
                 return Some(point);
             }
+            distance += 1;
             point.x = if let Some(x) = point.x.checked_add_signed(dx) {
                 x
             } else {
@@ -163,40 +843,86 @@ impl Canvas {
         None
     }
 
-    pub fn rect_around(&self, origin: UVec) -> Option<Rect> {
+    // Find the rect enclosing origin whose border is drawn with any of the given glyphs.
+    // Borders further than max_distance from origin are not considered.
+    pub fn rect_around(
+        &self,
+        origin: UVec,
+        styles: &[RectGlyphs],
+        max_distance: u16,
+    ) -> Option<Rect> {
         log::debug!("Finding rect around {origin:?}");
-        let horizontal = &[
-            Rect::HORIZONTAL,
-            Rect::TOP_LEFT,
-            Rect::TOP_RIGHT,
-            Rect::BOTTOM_LEFT,
-            Rect::BOTTOM_RIGHT,
-        ];
-        let vertical = &[
-            Rect::VERTICAL,
-            Rect::TOP_LEFT,
-            Rect::TOP_RIGHT,
-            Rect::BOTTOM_LEFT,
-            Rect::BOTTOM_RIGHT,
+        self.rect_from([origin; 4], styles, max_distance)
+    }
+
+    // Find the next rect enclosing inner, like rect_around, to select the box
+    // that a box is nested in.
+    pub fn rect_enclosing(
+        &self,
+        inner: &Rect,
+        styles: &[RectGlyphs],
+        max_distance: u16,
+    ) -> Option<Rect> {
+        log::debug!("Finding rect enclosing {inner:?}");
+        let (a, b) = (inner.top_left, inner.bottom_right);
+        let (x1, x2) = (a.x.min(b.x), a.x.max(b.x));
+        let (y1, y2) = (a.y.min(b.y), a.y.max(b.y));
+        // search outward from just outside each side
+        let starts = [
+            UVec {
+                x: x1,
+                y: y1.checked_sub(1)?,
+            },
+            UVec { x: x1, y: y2 + 1 },
+            UVec {
+                x: x1.checked_sub(1)?,
+                y: y1,
+            },
+            UVec { x: x2 + 1, y: y1 },
         ];
+        self.rect_from(starts, styles, max_distance)
+    }
+
+    // Find a rect by searching for its top, bottom, left, and right borders
+    // from each of starts.
+    fn rect_from(
+        &self,
+        [up, down, left, right]: [UVec; 4],
+        styles: &[RectGlyphs],
+        max_distance: u16,
+    ) -> Option<Rect> {
+        let horizontal: Vec<char> = styles
+            .iter()
+            .flat_map(|g| g.corners().into_iter().chain([g.horizontal]))
+            .collect();
+        let vertical: Vec<char> = styles
+            .iter()
+            .flat_map(|g| g.corners().into_iter().chain([g.vertical]))
+            .collect();
 
-        let Some(top) = self.find(origin, 0, -1, horizontal) else {
-            log::debug!("No '{horizontal:?}' found above {origin:?}");
+        let Some(top) = self.find(up, 0, -1, &horizontal, max_distance) else {
+            log::debug!("No '{horizontal:?}' found above {up:?}");
             return None;
         };
-        let Some(bottom) = self.find(origin, 0, 1, horizontal) else {
-            log::debug!("No '{horizontal:?}' found below {origin:?}");
+        let Some(bottom) = self.find(down, 0, 1, &horizontal, max_distance) else {
+            log::debug!("No '{horizontal:?}' found below {down:?}");
             return None;
         };
-        let Some(left) = self.find(origin, -1, 0, vertical) else {
-            log::debug!("No '{vertical:?}' found left of {origin:?}");
+        let Some(left) = self.find(left, -1, 0, &vertical, max_distance) else {
+            log::debug!("No '{vertical:?}' found left of {left:?}");
             return None;
         };
-        let Some(right) = self.find(origin, 1, 0, vertical) else {
-            log::debug!("No '{vertical:?}' found right of {origin:?}");
+        let Some(right) = self.find(right, 1, 0, &vertical, max_distance) else {
+            log::debug!("No '{vertical:?}' found right of {right:?}");
             return None;
         };
 
+        // on a tiny canvas, or at a corner, the same border may be found twice
+        if top.y >= bottom.y || left.x >= right.x {
+            log::debug!("No space between borders {top:?} {bottom:?} {left:?} {right:?}");
+            return None;
+        }
+
         let top_left = UVec {
             x: left.x,
             y: top.y,
@@ -214,37 +940,217 @@ impl Canvas {
             y: bottom.y,
         };
 
-        if self.get(top_left) != Rect::TOP_LEFT {
-            log::debug!("No rect corner found at {top_left:?}");
+        // each box must be matched by its own set of corners
+        let corners = [
+            self.get(top_left),
+            self.get(top_right),
+            self.get(bottom_left),
+            self.get(bottom_right),
+        ];
+        // styles may share corners, like square and dashed, so prefer the
+        // one with the border found between them
+        let edge = self.get(UVec {
+            x: left.x + 1,
+            y: top.y,
+        });
+        let Some(glyphs) = styles
+            .iter()
+            .filter(|g| g.corners() == corners)
+            .min_by_key(|g| g.horizontal != edge)
+        else {
+            log::debug!("No rect corners found at {top_left:?} {bottom_right:?}: {corners:?}");
             return None;
+        };
+
+        Some(Rect {
+            top_left,
+            bottom_right,
+            glyphs: *glyphs,
+            shadow: false,
+        })
+    }
+
+    // Where to jump to from point in direction d, like a word motion: onto the
+    // next content from empty space, to the end of a run of content from
+    // within it, or across a gap from its end. Stops at the edge of the canvas.
+    pub fn jump(&self, point: UVec, d: IVec) -> UVec {
+        let (size_y, size_x) = self.size();
+        let inside = |p: UVec| (p.x as usize) < size_x && (p.y as usize) < size_y;
+        let step = |p: UVec| Some(p + d).filter(|n| *n != p && inside(*n));
+        if !inside(point) {
+            return point;
         }
-        if self.get(top_right) != Rect::TOP_RIGHT {
-            log::debug!("No rect corner found at {top_right:?}");
-            return None;
+
+        let Some(mut p) = step(point) else {
+            return point;
+        };
+        let filled = |p: UVec| self.get(p) != EMPTY;
+        if filled(point) && filled(p) {
+            // to the end of this run
+            while let Some(n) = step(p).filter(|n| filled(*n)) {
+                p = n;
+            }
+            return p;
         }
-        if self.get(bottom_left) != Rect::BOTTOM_LEFT {
-            log::debug!("No rect corner found at {bottom_left:?}");
-            return None;
+        // on to the next content
+        while !filled(p) {
+            match step(p) {
+                Some(n) => p = n,
+                None => break,
+            }
+        }
+        p
+    }
+
+    // Where each label starts, in reading order. A label is a run of text, as
+    // opposed to lines and arrowheads, with words split by no more than one
+    // space.
+    pub fn labels(&self) -> Vec<UVec> {
+        let text = |c: char| c != EMPTY && !Line::is_line_char(c) && !"<>^▲▼◀▶".contains(c);
+        let mut starts = vec![];
+        for (y, row) in self.current.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let joined = match x {
+                    0 => false,
+                    1 => text(row[0]),
+                    _ => text(row[x - 1]) || (row[x - 1] == EMPTY && text(row[x - 2])),
+                };
+                if text(*c) && !joined {
+                    starts.push(UVec {
+                        x: x as u16,
+                        y: y as u16,
+                    });
+                }
+            }
         }
-        if self.get(bottom_right) != Rect::BOTTOM_RIGHT {
-            log::debug!("No rect corner found at {bottom_right:?}");
+        starts
+    }
+
+    // Find the closest of chars at most radius cells from point in any
+    // direction, preferring the lowest manhattan distance.
+    pub fn nearest(&self, point: UVec, radius: u16, chars: &[char]) -> Option<UVec> {
+        let (size_y, size_x) = self.size();
+        let (x1, y1) = (
+            point.x.saturating_sub(radius),
+            point.y.saturating_sub(radius),
+        );
+        let x2 = std::cmp::min(
+            point.x.saturating_add(radius) as usize,
+            size_x.checked_sub(1)?,
+        );
+        let y2 = std::cmp::min(
+            point.y.saturating_add(radius) as usize,
+            size_y.checked_sub(1)?,
+        );
+        (y1..=y2 as u16)
+            .flat_map(|y| (x1..=x2 as u16).map(move |x| UVec { x, y }))
+            .filter(|p| chars.contains(&self.get(*p)))
+            .min_by_key(|p| p.manhattan(point))
+    }
+
+    // Find the run of text around point, bounded by spaces or line characters.
+    pub fn text_run_at(&self, point: UVec) -> Option<Text> {
+        let (size_y, size_x) = self.size();
+        if point.x as usize >= size_x || point.y as usize >= size_y {
             return None;
         }
 
-        Some(Rect {
-            top_left,
-            bottom_right,
-        })
+        let row = &self.current[point.y as usize];
+        let is_text = |c: &char| *c != EMPTY && !Line::is_line_char(*c);
+        let x = point.x as usize;
+        if !is_text(&row[x]) {
+            return None;
+        }
+        let start = x - row[..x].iter().rev().take_while(|c| is_text(c)).count();
+        let end = x + row[x..].iter().take_while(|c| is_text(c)).count();
+        let text: String = row[start..end].iter().collect();
+        log::debug!("Found text {text:?} at {point:?}");
+        Some(Text::new(start as u16, point.y, &text))
     }
 
-    pub fn line_at(&self, origin: UVec) -> Option<Line> {
-        let Some(top) = self.find(origin, 0, -1, &[Line::CORNER]) else {
+    // Find the line with an endpoint at origin, returned with origin as its end.
+    // The line must be drawn entirely in one of styles.
+    pub fn line_at(&self, origin: UVec, styles: &[LineGlyphs]) -> Option<Line> {
+        let (size_y, size_x) = self.size();
+        let in_bounds = |p: UVec| (p.x as usize) < size_x && (p.y as usize) < size_y;
+        if !in_bounds(origin) {
             return None;
+        }
+
+        let g = *styles.iter().find(|g| g.corner == self.get(origin))?;
+
+        // the character that continues a line in direction d
+        let straight = |d: IVec| if d.x == 0 { g.vertical } else { g.horizontal };
+        let step = |p: UVec, d: IVec| {
+            let p = UVec {
+                x: p.x.checked_add_signed(d.x)?,
+                y: p.y.checked_add_signed(d.y)?,
+            };
+            in_bounds(p).then_some(p)
+        };
+        let connects =
+            |p: UVec, d: IVec, c: &[char]| step(p, d).is_some_and(|n| c.contains(&self.get(n)));
+        // follow a segment to the corner at its end
+        let walk = |mut p: UVec, d: IVec| loop {
+            p = step(p, d)?;
+            match self.get(p) {
+                c if c == g.corner => return Some(p),
+                c if c == straight(d) => continue,
+                _ => return None,
+            }
         };
-        let Some(bottom) = self.find(origin, 0, 1, &[Line::CORNER]) else {
+
+        let mut dirs = [IVec::UP, IVec::DOWN, IVec::LEFT, IVec::RIGHT]
+            .into_iter()
+            .filter(|d| connects(origin, *d, &[straight(*d), g.corner]));
+        let (Some(d), None) = (dirs.next(), dirs.next()) else {
+            log::debug!("No line endpoint at {origin:?}");
             return None;
         };
-        None
+
+        let bend = walk(origin, d)?;
+        // the line may turn at the bend
+        let turn = IVec { x: d.y, y: d.x };
+        let turn = [turn, -turn]
+            .into_iter()
+            .find(|t| connects(bend, *t, &[straight(*t)]));
+        let start = match turn {
+            Some(t) => walk(bend, t)?,
+            None => bend,
+        };
+
+        Some(Line {
+            start,
+            end: origin,
+            // the segment leading to the end is drawn last
+            mirror: d.x == 0,
+            glyphs: g,
+            dimension: false,
+        })
+    }
+
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.current
+    }
+
+    pub fn dimensions(&self) -> UVec {
+        let (size_y, size_x) = self.size();
+        UVec {
+            x: size_x as u16,
+            y: size_y as u16,
+        }
+    }
+
+    // The size of the smallest canvas that holds all non-empty cells.
+    fn used(&self) -> UVec {
+        let mut used = UVec::default();
+        for (y, row) in self.current.iter().enumerate() {
+            if let Some(x) = row.iter().rposition(|c| *c != EMPTY) {
+                used.x = used.x.max(x as u16 + 1);
+                used.y = y as u16 + 1;
+            }
+        }
+        used
     }
 
     // Returns (size_y, size_x).
@@ -267,29 +1173,168 @@ impl Canvas {
         }
     }
 
-    fn put(&mut self, x: u16, y: u16, c: char) -> char {
+    // Colors drawn over are moved to colors, for undo to put back.
+    fn put(
+        &mut self,
+        x: u16,
+        y: u16,
+        c: char,
+        join: bool,
+        colors: &mut Vec<((u16, u16), Color)>,
+    ) -> char {
+        let cell = &mut self.current[y as usize][x as usize];
+        let c = if c.is_control() {
+            log::warn!("Replacing control character {c:?} at {x},{y}");
+            PLACEHOLDER
+        } else if join {
+            Line::junction(*cell, c).unwrap_or(c)
+        } else {
+            c
+        };
         log::trace!("Putting {c} at {x},{y}");
-        std::mem::replace(&mut self.current[y as usize][x as usize], c)
+        colors.extend(self.colors.remove(&(x, y)).map(|color| ((x, y), color)));
+        std::mem::replace(cell, c)
     }
 
-    pub fn to_string(&self) -> String {
-        self.current
-            .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n")
-    }
-}
+    // Place other to the right of this canvas, gap columns apart, as a new
+    // canvas as tall as the taller of the two.
+    pub fn hconcat(&self, other: &Canvas, gap: usize) -> Canvas {
+        let (size_y, size_x) = self.size();
+        let (other_y, other_x) = other.size();
+        let width = size_x + gap + other_x;
+        let empty = vec![];
+        let current = (0..size_y.max(other_y))
+            .map(|y| {
+                let mut row = self.current.get(y).unwrap_or(&empty).clone();
+                row.resize(size_x + gap, EMPTY);
+                row.extend(other.current.get(y).unwrap_or(&empty));
+                row.resize(width, EMPTY);
+                row
+            })
+            .collect();
+        Canvas {
+            current,
+            ..Default::default()
+        }
+    }
+
+    // Stamp other onto this canvas with its top left at `at`, growing to fit,
+    // as one edit. If transparent, blank cells of other leave what is beneath.
+    // Returns false if nothing changed.
+    pub fn overlay(&mut self, other: &Canvas, at: UVec, transparent: bool) -> bool {
+        log::debug!("Overlaying canvas at {at:?}");
+        self.edit(Edit::stamp(at, &other.current, transparent).into_iter())
+    }
+
+    // A copy of the canvas with only its lines and box borders, and everything
+    // else, like labels, left blank.
+    pub fn skeleton(&self) -> Canvas {
+        let current = self
+            .current
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|c| if Line::is_line_char(*c) { *c } else { EMPTY })
+                    .collect()
+            })
+            .collect();
+        Canvas {
+            current,
+            ..Default::default()
+        }
+    }
+
+    // The edits that turn this canvas into other: one per run of differing
+    // cells in each row, with cells beyond the edge of either counted as empty.
+    // Applying them with `edit` turns this canvas into other.
+    pub fn diff(&self, other: &Canvas) -> Vec<Edit> {
+        let at = |canvas: &Canvas, x: usize, y: usize| {
+            canvas
+                .current
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(EMPTY)
+        };
+        let (size_y, size_x) = self.size();
+        let (other_y, other_x) = other.size();
+        let mut edits = vec![];
+        for y in 0..size_y.max(other_y) {
+            let mut run: Option<(usize, Vec<char>)> = None;
+            for x in 0..=size_x.max(other_x) {
+                let (from, to) = (at(self, x, y), at(other, x, y));
+                if from != to {
+                    run.get_or_insert_with(|| (x, vec![])).1.push(to);
+                } else if let Some((start, chars)) = run.take() {
+                    edits.push(Edit::Right {
+                        start: UVec {
+                            x: start as u16,
+                            y: y as u16,
+                        },
+                        chars,
+                    });
+                }
+            }
+        }
+        log::debug!("Found {} differing runs", edits.len());
+        edits
+    }
+
+    // Shrink the canvas by scale, marking each block of scale.x by scale.y cells
+    // that has anything drawn in it.
+    pub fn downscale(&self, scale: UVec) -> Vec<Vec<bool>> {
+        let (sx, sy) = (scale.x.max(1) as usize, scale.y.max(1) as usize);
+        let (_, size_x) = self.size();
+        self.current
+            .chunks(sy)
+            .map(|rows| {
+                (0..size_x.div_ceil(sx))
+                    .map(|bx| {
+                        rows.iter()
+                            .flat_map(|row| row.iter().skip(bx * sx).take(sx))
+                            .any(|c| *c != EMPTY)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Count each distinct non-space character on the canvas.
+    pub fn char_histogram(&self) -> BTreeMap<char, usize> {
+        let mut counts = BTreeMap::new();
+        for c in self.current.iter().flatten().filter(|c| **c != EMPTY) {
+            *counts.entry(*c).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn to_string(&self) -> String {
+        self.current
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // The content with ANSI escapes for the colors it was loaded with.
+    pub fn to_ansi_string(&self) -> String {
+        ansi::write(&self.to_string(), &self.colors)
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{rect::Rect, vec::UVec};
+    use crate::{
+        line::Weight,
+        rect::{Rect, RectStyle},
+        vec::UVec,
+    };
     use pretty_assertions::assert_eq;
 
     #[test]
     fn test_canvas_edit() {
-        let _ = env_logger::builder().is_test(true).try_init();
+        crate::test_log::init();
         let mut c = Canvas::new(4, 4);
         c.edit(
             vec![
@@ -314,9 +1359,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_canvas_draw_joins() {
+        let double = || {
+            std::iter::once(Edit::Right {
+                start: UVec::default(),
+                chars: vec!['═'],
+            })
+        };
+        let mut canvas = Canvas::from_str("|");
+        canvas.edit(double());
+        assert_eq!(canvas.to_string(), "═");
+
+        let mut canvas = Canvas::from_str("|");
+        canvas.draw(double());
+        assert_eq!(canvas.to_string(), "╪");
+    }
+
     #[test]
     fn test_canvas_undo_redo() {
-        let _ = env_logger::builder().is_test(true).try_init();
+        crate::test_log::init();
         let mut c = Canvas::new(4, 4);
 
         let state0 = c.to_string();
@@ -374,7 +1436,7 @@ mod tests {
 
     #[test]
     fn test_canvas_edit_clears_redo() {
-        let _ = env_logger::builder().is_test(true).try_init();
+        crate::test_log::init();
         let mut c = Canvas::new(4, 4);
 
         let state0 = c.to_string();
@@ -430,6 +1492,245 @@ mod tests {
         assert_eq!(c.to_string(), state2);
     }
 
+    #[test]
+    fn test_canvas_edit_noop() {
+        let mut c = Canvas::new(4, 4);
+        let rect = Rect::new(0, 0, 2, 2);
+        assert!(c.edit(rect.edits().into_iter()));
+        assert!(!c.edit(rect.edits().into_iter()));
+        assert_eq!(c.undo.len(), 1);
+    }
+
+    #[test]
+    fn test_canvas_edit_merge() {
+        let mut c = Canvas::new(4, 4);
+        c.set_merge_window(Duration::from_secs(60));
+        let state0 = c.to_string();
+
+        let points = [(0, 0), (2, 0), (2, 2), (0, 2)];
+        for [a, b] in points.array_windows() {
+            let line = Line::new(UVec { x: a.0, y: a.1 }, UVec { x: b.0, y: b.1 });
+            c.edit(line.edits().into_iter());
+        }
+        // not adjacent to the others
+        c.edit(Text::new(3, 3, "x").edits().into_iter());
+        assert_eq!(c.undo.len(), 2);
+
+        c.undo();
+        assert_eq!(c.to_string(), "+-+ \n  | \n+-+ \n    ");
+
+        c.undo();
+        assert_eq!(c.to_string(), state0);
+
+        c.redo();
+        assert_eq!(c.to_string(), "+-+ \n  | \n+-+ \n    ");
+    }
+
+    #[test]
+    fn test_canvas_resize() {
+        let mut c = Canvas::new(4, 3);
+        c.edit(Text::new(0, 0, "abcd\nefgh\nijkl").edits().into_iter());
+        let before = c.to_string();
+
+        assert!(c.resize(UVec { x: 2, y: 2 }));
+        assert_eq!(c.to_string(), "ab\nef");
+
+        assert!(c.resize(UVec { x: 3, y: 3 }));
+        assert_eq!(c.to_string(), "ab \nef \n   ");
+        assert!(!c.resize(UVec { x: 3, y: 3 }));
+
+        c.undo();
+        assert_eq!(c.to_string(), "ab\nef");
+
+        c.undo();
+        assert_eq!(c.to_string(), before);
+
+        c.redo();
+        assert_eq!(c.to_string(), "ab\nef");
+
+        c.redo();
+        assert_eq!(c.to_string(), "ab \nef \n   ");
+    }
+
+    #[test]
+    fn test_canvas_from_str() {
+        let c = Canvas::from_str("ab\ncde\n");
+        assert_eq!(c.dimensions(), UVec { x: 3, y: 2 });
+        assert_eq!(c.to_string(), "ab \ncde");
+    }
+
+    #[test]
+    fn test_canvas_from_str_trailing_lines() {
+        // the last newline ends the last row, and blank rows before it are kept
+        let c = Canvas::from_str("a\n\n\n");
+        assert_eq!(c.dimensions(), UVec { x: 1, y: 3 });
+        assert_eq!(c.to_string(), "a\n \n ");
+    }
+
+    #[test]
+    fn test_canvas_from_str_line_endings() {
+        let c = Canvas::from_str("a\rb\r\nc\n");
+        assert_eq!(c.dimensions(), UVec { x: 1, y: 3 });
+        assert_eq!(c.to_string(), "a\nb\nc");
+    }
+
+    #[test]
+    fn test_canvas_long_lines() {
+        let s = format!("abc\n{}\ndef\n{}", "x".repeat(5000), "y".repeat(11));
+        assert_eq!(Canvas::long_lines(&s, 10), vec![(2, 5000), (4, 11)]);
+        assert!(Canvas::long_lines(&s, 5000).is_empty());
+    }
+
+    #[test]
+    fn test_canvas_reset() {
+        let mut c = Canvas::new(4, 3);
+        c.edit(Text::new(0, 0, "abcd\nefgh\nijkl").edits().into_iter());
+        let before = c.to_string();
+
+        c.reset(UVec { x: 2, y: 2 });
+        assert_eq!(c.to_string(), "  \n  ");
+
+        c.undo();
+        assert_eq!(c.to_string(), before);
+
+        c.redo();
+        assert_eq!(c.to_string(), "  \n  ");
+    }
+
+    #[test]
+    fn test_canvas_json() {
+        let mut c = Canvas::new(4, 2);
+        c.edit(Rect::new(1, 0, 3, 2).edits().into_iter());
+
+        let json = c.to_json().unwrap();
+        assert!(json.starts_with(r#"{"width":4,"height":3,"rows":[[" ","+","-","+"]"#));
+
+        let actual = Canvas::from_json(&json).unwrap();
+        assert_eq!(actual.to_string(), c.to_string());
+    }
+
+    #[test]
+    fn test_canvas_json_invalid() {
+        assert!(Canvas::from_json(r#"{"width":2,"height":1,"rows":[]}"#).is_err());
+        assert!(Canvas::from_json(r#"{"width":2,"height":1,"rows":[["a"]]}"#).is_err());
+        assert!(Canvas::from_json(r#"{"width":1,"height":1,"rows":[["ab"]]}"#).is_err());
+    }
+
+    #[test]
+    fn test_char_histogram() {
+        let mut c = Canvas::new(8, 8);
+        c.edit(Rect::new(1, 1, 5, 4).edits().into_iter());
+        c.edit(Text::new(2, 2, "ab").edits().into_iter());
+        assert_eq!(
+            c.char_histogram(),
+            BTreeMap::from([('+', 4), ('-', 6), ('|', 4), ('a', 1), ('b', 1)])
+        );
+    }
+
+    #[test]
+    fn test_line_at() {
+        let mut c = Canvas::new(8, 8);
+        c.edit(
+            Line::new(UVec { x: 1, y: 1 }, UVec { x: 5, y: 3 })
+                .edits()
+                .into_iter(),
+        );
+        let styles = [LineGlyphs::SINGLE, LineGlyphs::DOUBLE];
+
+        let l = c.line_at(UVec { x: 5, y: 3 }, &styles).unwrap();
+        assert_eq!(
+            (l.start, l.end, l.mirror),
+            (UVec { x: 1, y: 1 }, UVec { x: 5, y: 3 }, false)
+        );
+
+        let l = c.line_at(UVec { x: 1, y: 1 }, &styles).unwrap();
+        assert_eq!(
+            (l.start, l.end, l.mirror),
+            (UVec { x: 5, y: 3 }, UVec { x: 1, y: 1 }, true)
+        );
+
+        // not an endpoint
+        assert!(c.line_at(UVec { x: 1, y: 3 }, &styles).is_none());
+        assert!(c.line_at(UVec { x: 3, y: 3 }, &styles).is_none());
+        assert!(c.line_at(UVec { x: 0, y: 0 }, &styles).is_none());
+    }
+
+    #[test]
+    fn test_jump() {
+        let c = Canvas::from_str("  +--+  ab  ");
+        let right = |x| c.jump(UVec { x, y: 0 }, IVec::RIGHT).x;
+        let left = |x| c.jump(UVec { x, y: 0 }, IVec::LEFT).x;
+        // onto the box, across it, then across the gap
+        assert_eq!(right(0), 2);
+        assert_eq!(right(2), 5);
+        assert_eq!(right(5), 8);
+        assert_eq!(right(9), 11);
+        assert_eq!(right(11), 11);
+        assert_eq!(left(11), 9);
+        assert_eq!(left(8), 5);
+        assert_eq!(left(5), 2);
+        assert_eq!(left(2), 0);
+        assert_eq!(left(0), 0);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut c = Canvas::new(8, 8);
+        c.edit(Rect::new(0, 0, 3, 2).edits().into_iter());
+        let corners = RectGlyphs::ASCII.corners();
+
+        assert_eq!(
+            c.nearest(UVec { x: 4, y: 2 }, 1, &corners),
+            Some(UVec { x: 3, y: 2 })
+        );
+        assert_eq!(
+            c.nearest(UVec { x: 1, y: 1 }, 1, &corners),
+            Some(UVec { x: 0, y: 0 })
+        );
+        assert_eq!(c.nearest(UVec { x: 5, y: 4 }, 1, &corners), None);
+        assert_eq!(
+            Canvas::new(0, 0).nearest(UVec::default(), 4, &corners),
+            None
+        );
+    }
+
+    #[test]
+    fn test_text_run_at() {
+        let mut c = Canvas::new(0, 0);
+        c.edit(Text::new(0, 1, "foo |bar|").edits().into_iter());
+        let run = |x| c.text_run_at(UVec { x, y: 1 }).map(|t| (t.start.x, t.text));
+
+        assert_eq!(run(0), Some((0, "foo".into())));
+        assert_eq!(run(2), Some((0, "foo".into())));
+        assert_eq!(run(5), Some((5, "bar".into())));
+        assert_eq!(run(7), Some((5, "bar".into())));
+
+        // spaces, line chars, and out of bounds
+        assert_eq!(run(3), None);
+        assert_eq!(run(4), None);
+        assert_eq!(run(8), None);
+        assert_eq!(run(9), None);
+        assert!(c.text_run_at(UVec { x: 0, y: 0 }).is_none());
+    }
+
+    #[test]
+    fn test_line_at_straight() {
+        let mut c = Canvas::new(8, 8);
+        c.edit(
+            Line::new(UVec { x: 2, y: 6 }, UVec { x: 2, y: 1 })
+                .edits()
+                .into_iter(),
+        );
+        let styles = [LineGlyphs::SINGLE];
+
+        let l = c.line_at(UVec { x: 2, y: 1 }, &styles).unwrap();
+        assert_eq!((l.start, l.end), (UVec { x: 2, y: 6 }, UVec { x: 2, y: 1 }));
+
+        let mut redrawn = Canvas::new(8, 8);
+        redrawn.edit(l.edits().into_iter());
+        assert_eq!(redrawn.to_string(), c.to_string());
+    }
+
     #[test]
     fn test_match_rect() {
         let mut c = Canvas::new(16, 8);
@@ -448,8 +1749,405 @@ mod tests {
                     None
                 };
                 let point = UVec { x, y };
-                assert_eq!(c.rect_around(point), expected, "{point:?}");
+                assert_eq!(
+                    c.rect_around(point, &[RectGlyphs::ASCII], u16::MAX),
+                    expected,
+                    "{point:?}"
+                );
             }
         }
     }
+
+    #[test]
+    fn test_erase_lines() {
+        let mut c = Canvas::new(7, 3);
+        c.edit(Rect::new(0, 0, 6, 2).edits().into_iter());
+        c.edit(Text::new(2, 1, "foo").edits().into_iter());
+        let before = c.to_string();
+
+        c.erase_lines(&Rect::new(0, 0, 6, 2), ' ');
+        assert_eq!(
+            c.to_string(),
+            "       
+  foo  
+       "
+        );
+
+        c.undo();
+        assert_eq!(c.to_string(), before);
+    }
+
+    #[test]
+    fn test_repair_boxes() {
+        let mut c = Canvas::from_str(
+            "\
++---+ +--+
+|   |    |
++- -+ +--+
++  +      ",
+        );
+        let before = c.to_string();
+
+        assert!(c.repair_boxes(&[RectGlyphs::ASCII]));
+        assert_eq!(
+            c.to_string(),
+            "\
++---+ +--+
+|   | |  |
++---+ +--+
++  +      "
+        );
+
+        // nothing left to repair
+        assert!(!c.repair_boxes(&[RectGlyphs::ASCII]));
+
+        c.undo();
+        assert_eq!(c.to_string(), before);
+    }
+
+    #[test]
+    fn test_history_roundtrip() {
+        let mut c = Canvas::new(4, 2);
+        c.edit(Text::new(0, 0, "ab").edits().into_iter());
+        c.edit(Text::new(0, 1, "cd").edits().into_iter());
+        c.undo();
+        let json = serde_json::to_string(&c.history()).unwrap();
+
+        let mut restored = Canvas::from_str(&c.to_string());
+        assert!(restored.restore_history(serde_json::from_str(&json).unwrap()));
+        restored.redo();
+        assert_eq!(restored.to_string(), "ab  \ncd  ");
+        restored.undo();
+        restored.undo();
+        assert_eq!(restored.to_string(), "    \n    ");
+
+        // history only applies to the content it was taken from
+        let mut other = Canvas::from_str("xy");
+        assert!(!other.restore_history(serde_json::from_str(&json).unwrap()));
+        other.undo();
+        assert_eq!(other.to_string(), "xy");
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut c = Canvas::from_str("ab");
+        c.edit(Text::new(0, 1, "cd").edits().into_iter());
+
+        c.shift(UVec { x: 1, y: 2 });
+        assert_eq!(c.to_string(), "   \n   \n ab\n cd");
+
+        // the history moves with the content
+        c.undo();
+        assert_eq!(c.to_string(), "   \n   \n ab");
+        c.redo();
+        assert_eq!(c.to_string(), "   \n   \n ab\n cd");
+    }
+
+    #[test]
+    fn test_hconcat() {
+        let a = Canvas::from_str("ab\ncd");
+        let b = Canvas::from_str("xyz");
+        let c = a.hconcat(&b, 1);
+        assert_eq!(c.dimensions(), UVec { x: 6, y: 2 });
+        assert_eq!(c.to_string(), "ab xyz\ncd    ");
+
+        let c = b.hconcat(&a, 0);
+        assert_eq!(c.to_string(), "xyzab\n   cd");
+    }
+
+    #[test]
+    fn test_overlay() {
+        let stamp = Canvas::from_str("+--+\n|  |\n+--+");
+        let mut c = Canvas::new(8, 5);
+        assert!(c.overlay(&stamp, UVec { x: 2, y: 1 }, false));
+        assert_eq!(
+            c.to_string(),
+            ["        ", "  +--+  ", "  |  |  ", "  +--+  ", "        "].join("\n")
+        );
+        c.undo();
+        assert_eq!(c.to_string().trim(), "");
+
+        // blank cells only cover what is beneath when opaque
+        let mut c = Canvas::from_str("xxxxxx\nxxxxxx\nxxxxxx");
+        c.overlay(&stamp, UVec { x: 1, y: 0 }, true);
+        assert_eq!(c.to_string(), "x+--+x\nx|xx|x\nx+--+x");
+        c.overlay(&stamp, UVec { x: 4, y: 1 }, false);
+        assert_eq!(c.dimensions(), UVec { x: 8, y: 4 });
+        assert_eq!(c.to_string(), "x+--+x  \nx|xx+--+\nx+--|  |\n    +--+");
+    }
+
+    #[test]
+    fn test_rotate() {
+        let rows = |s: &str| {
+            s.lines()
+                .map(|l| l.chars().collect())
+                .collect::<Vec<Vec<char>>>()
+        };
+        assert_eq!(Canvas::rotate(&rows("┌─▶\n│  ")), rows("─┐\n │\n ▼"));
+        assert_eq!(Canvas::rotate(&rows("a/\nb>")), rows("ba\nv\\"));
+        assert_eq!(Canvas::rotate(&[]), Vec::<Vec<char>>::new());
+    }
+
+    #[test]
+    fn test_edit_control_char() {
+        let mut c = Canvas::new(3, 1);
+        c.edit(
+            [Edit::Right {
+                start: UVec { x: 0, y: 0 },
+                chars: vec!['a', '\n', '\t'],
+            }]
+            .into_iter(),
+        );
+        assert_eq!(c.to_string(), "a\u{FFFD}\u{FFFD}");
+        assert_eq!(c.dimensions(), UVec { x: 3, y: 1 });
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = Canvas::from_str(
+            "\
+foo
+   bar",
+        );
+        let mut after = before.clone();
+        after.edit(Rect::new(1, 1, 4, 3).edits().into_iter());
+        assert_eq!(
+            after.to_string(),
+            ["foo   ", " +--+r", " |  | ", " +--+ "].join("\n")
+        );
+
+        let edits = before.diff(&after);
+        assert_eq!(
+            edits,
+            vec![
+                Edit::Right {
+                    start: UVec { x: 1, y: 1 },
+                    chars: "+--+".chars().collect(),
+                },
+                Edit::Right {
+                    start: UVec { x: 1, y: 2 },
+                    chars: vec!['|'],
+                },
+                Edit::Right {
+                    start: UVec { x: 4, y: 2 },
+                    chars: vec!['|'],
+                },
+                Edit::Right {
+                    start: UVec { x: 1, y: 3 },
+                    chars: "+--+".chars().collect(),
+                },
+            ]
+        );
+
+        let mut canvas = before.clone();
+        canvas.edit(edits.into_iter());
+        assert_eq!(canvas.to_string(), after.to_string());
+        assert_eq!(canvas.diff(&after), vec![]);
+    }
+
+    #[test]
+    fn test_labels() {
+        let c = Canvas::from_str(
+            "\
++-------+  note
+| a box |--+
++-------+  x  y",
+        );
+        assert_eq!(
+            c.labels(),
+            vec![
+                UVec { x: 11, y: 0 },
+                UVec { x: 2, y: 1 },
+                UVec { x: 11, y: 2 },
+                UVec { x: 14, y: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undo_after_resize() {
+        let mut canvas = Canvas::from_str("abc");
+        canvas.edit(Text::new(5, 0, "x").edits().into_iter());
+        assert_eq!(canvas.to_string(), "abc  x");
+
+        // grown and drawn in without going through the undo history
+        canvas.resize_y(2, 6);
+        canvas.current[1][4] = 'y';
+
+        canvas.undo();
+        assert_eq!(canvas.to_string(), "abc  \n    y");
+        canvas.redo();
+        assert_eq!(canvas.to_string(), "abc  x\n    y ");
+    }
+
+    #[test]
+    fn test_restyle() {
+        let ascii = ["+--+   a-b", "|  |-->   ", "+--+ |  + ", "     +--+ "].join("\n");
+        let mut c = Canvas::from_str(&ascii);
+        assert!(c.restyle(&Theme::ASCII, &Theme::UNICODE));
+        assert_eq!(
+            c.to_string(),
+            ["┌──┐   a-b", "│  │──▶   ", "└──┘ │  ┼ ", "     ┼──┼ ",].join("\n")
+        );
+
+        assert!(c.restyle(&Theme::UNICODE, &Theme::ASCII));
+        assert_eq!(c.to_string(), ascii);
+    }
+
+    #[test]
+    fn test_downscale() {
+        let c = Canvas::from_str(
+            "\
+ab
+      x
+
+   y",
+        );
+        assert_eq!(
+            c.downscale(UVec { x: 3, y: 2 }),
+            vec![vec![true, false, true], vec![false, true, false]]
+        );
+        assert_eq!(c.downscale(UVec { x: 7, y: 4 }), vec![vec![true]]);
+    }
+
+    #[test]
+    fn test_skeleton() {
+        let c = Canvas::from_str(
+            "\
++-----+  ┌──┐
+| foo |--│hi│
++-----+  └──┘",
+        );
+        assert_eq!(
+            c.skeleton().to_string(),
+            "\
++-----+  ┌──┐
+|     |--│  │
++-----+  └──┘"
+        );
+    }
+
+    #[test]
+    fn test_erase_region() {
+        let mut c = Canvas::new(4, 3);
+        c.edit(Text::new(0, 0, "abcd\nefgh\nijkl").edits().into_iter());
+        let before = c.to_string();
+
+        // extends past the right edge
+        c.erase_region(&Rect::new(5, 1, 2, 0), ' ');
+        assert_eq!(c.to_string(), "ab  \nef  \nijkl");
+
+        c.undo();
+        assert_eq!(c.to_string(), before);
+    }
+
+    #[test]
+    fn test_rect_enclosing() {
+        let c = Canvas::from_str(
+            "\
++----------+
+| +------+ |
+| | +--+ | |
+| | |  | | |
+| | +--+ | |
+| +------+ |
++----------+",
+        );
+        let styles = [RectGlyphs::ASCII];
+        let inner = Rect::new(4, 2, 7, 4);
+        let middle = Rect::new(2, 1, 9, 5);
+        let outer = Rect::new(0, 0, 11, 6);
+
+        assert_eq!(
+            c.rect_around(UVec { x: 5, y: 3 }, &styles, u16::MAX),
+            Some(inner)
+        );
+        assert_eq!(c.rect_enclosing(&inner, &styles, u16::MAX), Some(middle));
+        assert_eq!(c.rect_enclosing(&middle, &styles, u16::MAX), Some(outer));
+        assert_eq!(c.rect_enclosing(&outer, &styles, u16::MAX), None);
+    }
+
+    #[test]
+    fn test_match_rect_empty() {
+        let styles = [RectGlyphs::ASCII];
+        let c = Canvas::default();
+        assert_eq!(c.rect_around(UVec { x: 0, y: 0 }, &styles, u16::MAX), None);
+        assert_eq!(c.rect_around(UVec { x: 3, y: 2 }, &styles, u16::MAX), None);
+
+        let c = Canvas::new(0, 4);
+        assert_eq!(c.rect_around(UVec { x: 0, y: 1 }, &styles, u16::MAX), None);
+
+        let mut c = Canvas::new(1, 1);
+        assert_eq!(c.rect_around(UVec { x: 0, y: 0 }, &styles, u16::MAX), None);
+        c.edit(Rect::new(0, 0, 0, 0).edits().into_iter());
+        assert_eq!(c.to_string(), "+");
+        assert_eq!(c.rect_around(UVec { x: 0, y: 0 }, &styles, u16::MAX), None);
+    }
+
+    #[test]
+    fn test_match_rect_max_distance() {
+        let mut c = Canvas::new(32, 16);
+        let near = Rect::new(0, 0, 4, 2);
+        let far = Rect::new(10, 0, 30, 12);
+        c.edit(near.edits().into_iter());
+        c.edit(far.edits().into_iter());
+
+        let styles = [RectGlyphs::ASCII];
+        assert_eq!(c.rect_around(UVec { x: 2, y: 1 }, &styles, 4), Some(near));
+        assert_eq!(c.rect_around(UVec { x: 20, y: 6 }, &styles, 4), None);
+        assert_eq!(c.rect_around(UVec { x: 20, y: 6 }, &styles, 10), Some(far));
+    }
+
+    #[test]
+    fn test_match_rect_mixed_styles() {
+        let mut c = Canvas::new(16, 8);
+        let square = Rect::new(1, 1, 5, 4);
+        let rounded = Rect {
+            glyphs: RectStyle::Rounded.glyphs(Weight::Single),
+            ..Rect::new(8, 2, 13, 6)
+        };
+        c.edit(square.edits().into_iter());
+        c.edit(rounded.edits().into_iter());
+
+        let styles = RectStyle::ALL.map(|s| s.glyphs(Weight::Single));
+        assert_eq!(
+            c.rect_around(UVec { x: 3, y: 2 }, &styles, u16::MAX),
+            Some(square)
+        );
+        assert_eq!(
+            c.rect_around(UVec { x: 10, y: 4 }, &styles, u16::MAX),
+            Some(rounded)
+        );
+
+        // the rounded box is not matched when only ascii is accepted
+        assert_eq!(
+            c.rect_around(UVec { x: 10, y: 4 }, &[RectGlyphs::ASCII], u16::MAX),
+            None
+        );
+    }
+
+    #[test]
+    fn test_match_rect_dashed() {
+        let mut c = Canvas::new(16, 8);
+        let solid = Rect {
+            glyphs: RectGlyphs::SQUARE,
+            ..Rect::new(1, 1, 5, 4)
+        };
+        let dashed = Rect {
+            glyphs: RectGlyphs::DASHED,
+            ..Rect::new(8, 2, 13, 6)
+        };
+        c.edit(solid.edits().into_iter());
+        c.edit(dashed.edits().into_iter());
+
+        let styles = [RectGlyphs::SQUARE, RectGlyphs::DASHED];
+        assert_eq!(
+            c.rect_around(UVec { x: 3, y: 2 }, &styles, u16::MAX),
+            Some(solid)
+        );
+        assert_eq!(
+            c.rect_around(UVec { x: 10, y: 4 }, &styles, u16::MAX),
+            Some(dashed)
+        );
+    }
 }
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::Result;
-use serde::Deserialize;
+use ratatui::style::{Color, Style, Stylize};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize)]
-#[cfg_attr(test, derive(PartialEq))]
+use crate::{line::Weight, rect::RectStyle, theme::ThemeConfig};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Action {
     Quit,
@@ -14,39 +16,231 @@ pub enum Action {
     MoveCursorDown,
     MoveCursorLeft,
     MoveCursorRight,
+    // Move to the next edge of content, e.g. bind S-d to jump_right instead of
+    // moving a fixed number of cells.
+    JumpUp,
+    JumpDown,
+    JumpLeft,
+    JumpRight,
+    // To the start of the next or previous text label, in reading order.
+    NextLabel,
+    PrevLabel,
+    // To the first non-blank cell of the row, or column 0 if already there.
+    SmartHome,
+    ExtendSelectionUp,
+    ExtendSelectionDown,
+    ExtendSelectionLeft,
+    ExtendSelectionRight,
 
     DrawRect,
     DrawLine,
+    // A straight line with end ticks, labeled with its length in cells.
+    DrawDimension,
     DrawText,
+    DrawTextVertical,
+    Paint,
+    DrawTable,
+    // Drag out a region to clear on confirm.
+    EraseRect,
     ExitMode,
+    ConfirmShape,
+    CancelShape,
 
     LineAddPoint,
+    LinePopPoint,
     LineMirror,
+    SwapAnchor,
+    // Draw the rect in progress as a window, with a title bar.
+    TitleRect,
     TextAddLine,
+    ToggleWeight,
 
     Delete,
+    Copy,
+    Paste,
     Undo,
     Redo,
+    // Undo or redo everything, to the start or end of the history.
+    UndoAll,
+    RedoAll,
+    // Apply the last action that was not a movement again, like vim's `.`.
+    RepeatLast,
 
     SelectRect,
+    // Turn the selected rect and its content a quarter turn clockwise.
+    RotateSelection,
+    // Redraw the selected rect's border over anything crossing it.
+    RaiseSelection,
+    SelectLine,
+    EraseLines,
+    RepairBoxes,
+
+    SetMark,
+    NewDocument,
+    Resize,
+    // Move everything drawn one cell, as one edit.
+    ShiftCanvasUp,
+    ShiftCanvasDown,
+    ShiftCanvasLeft,
+    ShiftCanvasRight,
+    CenterCursor,
+    GotoCoordinate,
+    ToggleBorder,
+    ToggleMinimap,
+    ToggleWhitespace,
+    // Switch between the ascii and unicode themes, redrawing what is drawn.
+    ToggleTheme,
+}
+
+impl Action {
+    // Every action, to find those that are not bound.
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::Save,
+        Action::MoveCursorUp,
+        Action::MoveCursorDown,
+        Action::MoveCursorLeft,
+        Action::MoveCursorRight,
+        Action::JumpUp,
+        Action::JumpDown,
+        Action::JumpLeft,
+        Action::JumpRight,
+        Action::NextLabel,
+        Action::PrevLabel,
+        Action::SmartHome,
+        Action::ExtendSelectionUp,
+        Action::ExtendSelectionDown,
+        Action::ExtendSelectionLeft,
+        Action::ExtendSelectionRight,
+        Action::DrawRect,
+        Action::DrawLine,
+        Action::DrawDimension,
+        Action::DrawText,
+        Action::DrawTextVertical,
+        Action::Paint,
+        Action::DrawTable,
+        Action::EraseRect,
+        Action::ExitMode,
+        Action::ConfirmShape,
+        Action::CancelShape,
+        Action::LineAddPoint,
+        Action::LinePopPoint,
+        Action::LineMirror,
+        Action::SwapAnchor,
+        Action::TitleRect,
+        Action::TextAddLine,
+        Action::ToggleWeight,
+        Action::Delete,
+        Action::Copy,
+        Action::Paste,
+        Action::Undo,
+        Action::Redo,
+        Action::UndoAll,
+        Action::RedoAll,
+        Action::RepeatLast,
+        Action::SelectRect,
+        Action::RotateSelection,
+        Action::RaiseSelection,
+        Action::SelectLine,
+        Action::EraseLines,
+        Action::RepairBoxes,
+        Action::SetMark,
+        Action::NewDocument,
+        Action::Resize,
+        Action::ShiftCanvasUp,
+        Action::ShiftCanvasDown,
+        Action::ShiftCanvasLeft,
+        Action::ShiftCanvasRight,
+        Action::CenterCursor,
+        Action::GotoCoordinate,
+        Action::ToggleBorder,
+        Action::ToggleMinimap,
+        Action::ToggleWhitespace,
+        Action::ToggleTheme,
+    ];
+
+    // Whether RepeatLast may repeat this, which excludes moving the cursor,
+    // moving through history, and leaving a mode, so that repeating after
+    // drawing a shape starts another.
+    pub fn repeatable(&self) -> bool {
+        !matches!(
+            self,
+            Action::Quit
+                | Action::Save
+                | Action::MoveCursorUp
+                | Action::MoveCursorDown
+                | Action::MoveCursorLeft
+                | Action::MoveCursorRight
+                | Action::JumpUp
+                | Action::JumpDown
+                | Action::JumpLeft
+                | Action::JumpRight
+                | Action::NextLabel
+                | Action::PrevLabel
+                | Action::SmartHome
+                | Action::CenterCursor
+                | Action::GotoCoordinate
+                | Action::ExitMode
+                | Action::ConfirmShape
+                | Action::CancelShape
+                | Action::Undo
+                | Action::Redo
+                | Action::UndoAll
+                | Action::RedoAll
+                | Action::RepeatLast
+        )
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[cfg_attr(test, derive(PartialEq, Clone))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
 #[serde(untagged)]
 pub enum Binding {
     Single(Action),
     Multi(Vec<Action>),
 }
 
-#[derive(Debug, Deserialize)]
-pub struct BindConfig(pub HashMap<String, Binding>);
+// Keys in the order they were given, so a key bound twice can be reported
+// rather than silently collapsed. A later bind for a key replaces an earlier.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(from = "HashMap<String, Binding>")]
+pub struct BindConfig(pub Vec<(String, Binding)>);
+
+impl From<HashMap<String, Binding>> for BindConfig {
+    fn from(m: HashMap<String, Binding>) -> Self {
+        Self(m.into_iter().collect())
+    }
+}
+
+impl Serialize for BindConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // sort by key so dumped configs are stable
+        self.0
+            .iter()
+            .map(|(k, v)| (k, v))
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for BindConfig {
+    // Equal if each key does the same, regardless of order.
+    fn eq(&self, other: &Self) -> bool {
+        let resolve = |b: &Self| b.0.iter().cloned().collect::<HashMap<_, _>>();
+        resolve(self) == resolve(other)
+    }
+}
 
 impl std::ops::Index<&str> for BindConfig {
     type Output = Binding;
 
     fn index(&self, index: &str) -> &Self::Output {
-        &self.0[index]
+        self.0
+            .iter()
+            .rev()
+            .find_map(|(k, v)| (k == index).then_some(v))
+            .unwrap_or_else(|| panic!("No bind for {index}"))
     }
 }
 
@@ -75,32 +269,335 @@ impl Default for BindConfig {
                     "S-d".to_string(),
                     Binding::Multi(vec![Action::MoveCursorRight; 4]),
                 ),
+                (
+                    "S-up".to_string(),
+                    Binding::Single(Action::ExtendSelectionUp),
+                ),
+                (
+                    "S-down".to_string(),
+                    Binding::Single(Action::ExtendSelectionDown),
+                ),
+                (
+                    "S-left".to_string(),
+                    Binding::Single(Action::ExtendSelectionLeft),
+                ),
+                (
+                    "S-right".to_string(),
+                    Binding::Single(Action::ExtendSelectionRight),
+                ),
                 // mode
                 ("r".to_string(), Binding::Single(Action::DrawRect)),
                 ("i".to_string(), Binding::Single(Action::DrawText)),
+                ("I".to_string(), Binding::Single(Action::DrawTextVertical)),
                 ("l".to_string(), Binding::Single(Action::DrawLine)),
+                ("p".to_string(), Binding::Single(Action::Paint)),
+                ("T".to_string(), Binding::Single(Action::DrawTable)),
+                ("E".to_string(), Binding::Single(Action::EraseRect)),
                 // line
                 (" ".to_string(), Binding::Single(Action::LineAddPoint)),
-                ("m".to_string(), Binding::Single(Action::LineMirror)),
+                (
+                    "backspace".to_string(),
+                    Binding::Single(Action::LinePopPoint),
+                ),
+                ("v".to_string(), Binding::Single(Action::LineMirror)),
+                ("o".to_string(), Binding::Single(Action::SwapAnchor)),
+                ("C-t".to_string(), Binding::Single(Action::TitleRect)),
+                ("t".to_string(), Binding::Single(Action::ToggleWeight)),
                 // general
                 ("x".to_string(), Binding::Single(Action::Delete)),
+                ("y".to_string(), Binding::Single(Action::Copy)),
+                ("C-v".to_string(), Binding::Single(Action::Paste)),
                 ("C-s".to_string(), Binding::Single(Action::Save)),
                 ("q".to_string(), Binding::Single(Action::Quit)),
-                ("esc".to_string(), Binding::Single(Action::ExitMode)),
-                ("enter".to_string(), Binding::Single(Action::ExitMode)),
+                ("esc".to_string(), Binding::Single(Action::CancelShape)),
+                // in text mode, enter adds a line unless bound to something
+                // other than confirm_shape, exit_mode, or text_add_line
+                ("enter".to_string(), Binding::Single(Action::ConfirmShape)),
                 ("u".to_string(), Binding::Single(Action::Undo)),
                 ("U".to_string(), Binding::Single(Action::Redo)),
+                (".".to_string(), Binding::Single(Action::RepeatLast)),
                 ("m".to_string(), Binding::Single(Action::SelectRect)),
+                ("C-r".to_string(), Binding::Single(Action::RotateSelection)),
+                ("F".to_string(), Binding::Single(Action::RaiseSelection)),
+                ("L".to_string(), Binding::Single(Action::SelectLine)),
+                ("X".to_string(), Binding::Single(Action::EraseLines)),
+                ("B".to_string(), Binding::Single(Action::RepairBoxes)),
+                ("M".to_string(), Binding::Single(Action::SetMark)),
+                ("R".to_string(), Binding::Single(Action::Resize)),
+                ("C-n".to_string(), Binding::Single(Action::NewDocument)),
+                ("z".to_string(), Binding::Single(Action::CenterCursor)),
+                ("g".to_string(), Binding::Single(Action::GotoCoordinate)),
+                ("home".to_string(), Binding::Single(Action::SmartHome)),
+                ("tab".to_string(), Binding::Single(Action::NextLabel)),
+                ("backtab".to_string(), Binding::Single(Action::PrevLabel)),
+                // view
+                ("C-up".to_string(), Binding::Single(Action::ShiftCanvasUp)),
+                (
+                    "C-down".to_string(),
+                    Binding::Single(Action::ShiftCanvasDown),
+                ),
+                (
+                    "C-left".to_string(),
+                    Binding::Single(Action::ShiftCanvasLeft),
+                ),
+                (
+                    "C-right".to_string(),
+                    Binding::Single(Action::ShiftCanvasRight),
+                ),
+                ("f2".to_string(), Binding::Single(Action::ToggleTheme)),
             ]
             .into(),
         )
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+// Where the cursor may move to in normal mode.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorConfinement {
+    // Anywhere.
+    #[default]
+    Free,
+    // No further right than the cell after the last character in a row.
+    Content,
+}
+
+// Colors and emphasis for part of the display. Colors are named, like "cyan",
+// or given in hex, like "#00ffff".
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StyleConfig {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+}
+
+impl StyleConfig {
+    pub fn style(&self) -> Style {
+        let mut style = Style::new();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+        if self.bold {
+            style = style.bold();
+        }
+        if self.italic {
+            style = style.italic();
+        }
+        if self.underlined {
+            style = style.underlined();
+        }
+        style
+    }
+}
+
+// How to mark the cell where the rect or line being drawn was started.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnchorMarker {
+    // Drawn over the shape at the anchor, if set.
+    pub char: Option<char>,
+    pub style: MarkerStyle,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerStyle {
+    // Drawn like the rest of the shape.
+    Plain,
+    Bold,
+    #[default]
+    Underlined,
+    Reversed,
+}
+
+// What confirming does in normal mode, where there is no shape to confirm.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalEnter {
+    #[default]
+    Nothing,
+    // Move the cursor to the start of the next row.
+    Newline,
+}
+
+// What happens to a shape in progress when starting to draw another.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModeSwitch {
+    // Drop it, with a warning.
+    #[default]
+    Discard,
+    // Draw it, as if it were confirmed first.
+    Confirm,
+}
+
+// Which leg of a new line is drawn first, and so loses where they meet.
+// LineMirror flips it for the line being drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineOrder {
+    #[default]
+    VerticalFirst,
+    HorizontalFirst,
+}
+
+// What to do when saving to a path that is a symlink.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkSave {
+    // Write to the file the link points to.
+    #[default]
+    Follow,
+    // Replace the link with a regular file.
+    Replace,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
 #[serde(default)]
 pub struct Config {
     pub binds: BindConfig,
+    pub rect_style: RectStyle,
+    pub weight: Weight,
+    // The glyphs used to draw single weight shapes.
+    pub theme: ThemeConfig,
+    // Draw new rects with a drop shadow.
+    pub shadow: bool,
+    // Cancelling twice in quick succession in normal mode quits, asking first
+    // if there are unsaved changes.
+    pub double_esc_quit: bool,
+    // Wrap the cursor to the opposite edge when moving past the canvas bounds.
+    pub wrap_cursor: bool,
+    // Grow the canvas when moving past the top or left edge, shifting what is
+    // already drawn down or right. It always grows to the bottom and right.
+    pub expand_up_left: bool,
+    pub cursor_confinement: CursorConfinement,
+    pub normal_enter: NormalEnter,
+    pub mode_switch: ModeSwitch,
+    pub line_order: LineOrder,
+    // How far from the cursor to look for the borders of a rect to select.
+    pub select_radius: u16,
+    // Start a free selection at the cursor when there is no rect to select.
+    pub select_fallback: bool,
+    // Confirming a rect or line starts drawing another at the cursor.
+    pub sticky_tools: bool,
+    pub symlink_save: SymlinkSave,
+    // Create missing parent directories when saving, like `mkdir -p`.
+    pub create_dirs: bool,
+    // The size of a new canvas.
+    pub new_width: u16,
+    pub new_height: u16,
+    // Where the cursor starts, kept within the canvas. Unset, a file opens at
+    // the cursor its last session left.
+    pub cursor: Option<crate::vec::UVec>,
+    // Crop trailing spaces and blank rows when loading, so the canvas ends at
+    // its content.
+    pub trim_on_load: bool,
+    // Warn when loading a file with a line longer than this.
+    pub max_line_length: usize,
+    // The file to edit when none is given.
+    pub default_path: Option<std::path::PathBuf>,
+    // Log a line at info level for each action, with the resulting mode and
+    // cursor. Also enabled by setting BOXT_LOG_ACTIONS.
+    pub log_actions: bool,
+    // Move the cursor faster while a movement key is repeated.
+    pub accelerate: bool,
+    // Start rects on an existing corner this many cells from the cursor.
+    pub corner_snap: u16,
+    // Constrain the end of a line being drawn to 45 or 90 degrees from its start.
+    pub snap_lines: bool,
+    // Confirming a line that ends where it starts draws nothing, rather than a
+    // lone corner.
+    pub skip_empty_lines: bool,
+    // Draw a border with a title and help around the canvas.
+    pub border: bool,
+    pub anchor_marker: AnchorMarker,
+    // How a selected rect or line is drawn while it is moved.
+    pub selection_style: StyleConfig,
+    // Number each row by its distance from the cursor row, in a gutter left of
+    // the canvas.
+    pub relative_numbers: bool,
+    // Show the whole canvas shrunk down in the top right, with the part on
+    // screen highlighted.
+    pub minimap: bool,
+    // Show each blank cell of the canvas as a faint dot. Only the display is
+    // affected, not what is saved.
+    pub show_whitespace: bool,
+    // Show the cell under the cursor in inverse video.
+    pub highlight_cursor: bool,
+    // Draw arrows typed as text, like `-->`, with line and arrow glyphs.
+    pub text_arrows: bool,
+    // Typed text flows past anything already drawn instead of overwriting it.
+    pub text_skip_occupied: bool,
+    // Wrap typed text at spaces to keep lines within this many columns.
+    pub text_wrap_width: Option<u16>,
+    // Written in place of anything erased, e.g. '.' to keep a dotted background.
+    pub erase_char: char,
+    // Spaces in pasted content leave what is under them.
+    pub transparent_paste: bool,
+    // Overlapping edits made within this many milliseconds are undone together.
+    pub undo_merge_ms: u64,
+    // Keep undo history beside the file on save, to undo after reopening it.
+    pub persist_undo: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            binds: BindConfig::default(),
+            rect_style: RectStyle::default(),
+            weight: Weight::default(),
+            theme: ThemeConfig::default(),
+            shadow: false,
+            double_esc_quit: false,
+            wrap_cursor: false,
+            expand_up_left: false,
+            cursor_confinement: CursorConfinement::default(),
+            normal_enter: NormalEnter::default(),
+            mode_switch: ModeSwitch::default(),
+            line_order: LineOrder::default(),
+            select_radius: 80,
+            select_fallback: false,
+            sticky_tools: false,
+            symlink_save: SymlinkSave::default(),
+            create_dirs: false,
+            new_width: 32,
+            new_height: 32,
+            cursor: Default::default(),
+            trim_on_load: false,
+            max_line_length: 1000,
+            default_path: None,
+            log_actions: false,
+            accelerate: false,
+            corner_snap: 0,
+            snap_lines: false,
+            skip_empty_lines: false,
+            border: true,
+            anchor_marker: AnchorMarker::default(),
+            selection_style: StyleConfig {
+                fg: Some(Color::Cyan),
+                bold: true,
+                ..Default::default()
+            },
+            relative_numbers: false,
+            minimap: false,
+            show_whitespace: false,
+            highlight_cursor: false,
+            text_arrows: false,
+            text_skip_occupied: false,
+            text_wrap_width: None,
+            erase_char: ' ',
+            transparent_paste: false,
+            undo_merge_ms: 0,
+            persist_undo: false,
+        }
+    }
 }
 
 impl Config {
@@ -108,12 +605,112 @@ impl Config {
         let c: Self = toml::from_str(s)?;
         Ok(c)
     }
+
+    pub fn dump(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_action_all() {
+        // a new action fails to compile here until it is given the next
+        // position, which fails the test until it is added to ALL
+        let position = |action: &Action| match action {
+            Action::Quit => 0,
+            Action::Save => 1,
+            Action::MoveCursorUp => 2,
+            Action::MoveCursorDown => 3,
+            Action::MoveCursorLeft => 4,
+            Action::MoveCursorRight => 5,
+            Action::JumpUp => 6,
+            Action::JumpDown => 7,
+            Action::JumpLeft => 8,
+            Action::JumpRight => 9,
+            Action::NextLabel => 10,
+            Action::PrevLabel => 11,
+            Action::SmartHome => 12,
+            Action::ExtendSelectionUp => 13,
+            Action::ExtendSelectionDown => 14,
+            Action::ExtendSelectionLeft => 15,
+            Action::ExtendSelectionRight => 16,
+            Action::DrawRect => 17,
+            Action::DrawLine => 18,
+            Action::DrawDimension => 19,
+            Action::DrawText => 20,
+            Action::DrawTextVertical => 21,
+            Action::Paint => 22,
+            Action::DrawTable => 23,
+            Action::EraseRect => 24,
+            Action::ExitMode => 25,
+            Action::ConfirmShape => 26,
+            Action::CancelShape => 27,
+            Action::LineAddPoint => 28,
+            Action::LinePopPoint => 29,
+            Action::LineMirror => 30,
+            Action::SwapAnchor => 31,
+            Action::TitleRect => 32,
+            Action::TextAddLine => 33,
+            Action::ToggleWeight => 34,
+            Action::Delete => 35,
+            Action::Copy => 36,
+            Action::Paste => 37,
+            Action::Undo => 38,
+            Action::Redo => 39,
+            Action::UndoAll => 40,
+            Action::RedoAll => 41,
+            Action::RepeatLast => 42,
+            Action::SelectRect => 43,
+            Action::RotateSelection => 44,
+            Action::RaiseSelection => 45,
+            Action::SelectLine => 46,
+            Action::EraseLines => 47,
+            Action::RepairBoxes => 48,
+            Action::SetMark => 49,
+            Action::NewDocument => 50,
+            Action::Resize => 51,
+            Action::ShiftCanvasUp => 52,
+            Action::ShiftCanvasDown => 53,
+            Action::ShiftCanvasLeft => 54,
+            Action::ShiftCanvasRight => 55,
+            Action::CenterCursor => 56,
+            Action::GotoCoordinate => 57,
+            Action::ToggleBorder => 58,
+            Action::ToggleMinimap => 59,
+            Action::ToggleWhitespace => 60,
+            Action::ToggleTheme => 61,
+        };
+        let positions: Vec<_> = Action::ALL.iter().map(position).collect();
+        assert_eq!(positions, (0..=61).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_config_selection_style() {
+        let c = Config::read(
+            r##"
+            [selection_style]
+            fg = "red"
+            bg = "#102030"
+            italic = true
+            "##,
+        )
+        .unwrap();
+        assert_eq!(
+            c.selection_style.style(),
+            Style::new()
+                .fg(Color::Red)
+                .bg(Color::Rgb(0x10, 0x20, 0x30))
+                .italic()
+        );
+        assert_eq!(
+            Config::default().selection_style.style(),
+            Style::new().fg(Color::Cyan).bold()
+        );
+    }
+
     #[test]
     fn test_config_binds() {
         let s = toml::toml! {
@@ -131,4 +728,22 @@ mod tests {
         assert_eq!(b.0["C-c"], Binding::Multi(vec![Action::Save, Action::Quit]));
         assert_eq!(b.0["s"], Binding::Single(Action::Save));
     }
+
+    #[test]
+    fn test_config_rect_style() {
+        let s = toml::toml! {
+            rect_style = "rounded"
+        }
+        .to_string();
+
+        let c = Config::read(&s).unwrap();
+        assert_eq!(c.rect_style, RectStyle::Rounded);
+    }
+
+    #[test]
+    fn test_config_dump() {
+        let c = Config::default();
+        let s = c.dump().unwrap();
+        assert_eq!(Config::read(&s).unwrap(), c);
+    }
 }
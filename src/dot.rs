@@ -0,0 +1,193 @@
+use anyhow::{bail, Result};
+
+use crate::{
+    canvas::Canvas, edit::Edit, line::Line, rect::Rect, text::Text, theme::Theme, vec::UVec,
+};
+
+// Columns left between adjacent nodes.
+const GAP: u16 = 4;
+
+// A minimal graphviz graph: just node names and the edges between them.
+#[derive(Debug, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct Graph {
+    pub nodes: Vec<String>,
+    pub edges: Vec<(usize, usize)>,
+    pub directed: bool,
+}
+
+impl Graph {
+    // Parse statements like `a -> b -> c;` or `a;` from a DOT graph.
+    // Attributes, subgraphs, and ports are not supported.
+    pub fn parse(s: &str) -> Result<Graph> {
+        let (Some(open), Some(close)) = (s.find('{'), s.rfind('}')) else {
+            bail!("Expected a graph body enclosed in braces");
+        };
+        if close < open {
+            bail!("Expected a graph body enclosed in braces");
+        }
+
+        let mut graph = Graph {
+            directed: s[..open].contains("digraph"),
+            ..Default::default()
+        };
+
+        for stmt in s[open + 1..close].split(|c| c == ';' || c == '\n') {
+            // drop attribute lists
+            let stmt = stmt.split('[').next().unwrap_or_default().trim();
+            if stmt.is_empty()
+                || stmt.starts_with("//")
+                || stmt.starts_with('#')
+                || stmt.contains('=')
+                || ["graph", "node", "edge"].contains(&stmt)
+            {
+                log::debug!("Skipping DOT statement: {stmt:?}");
+                continue;
+            }
+
+            let mut ids = vec![];
+            for id in stmt.split("->").flat_map(|s| s.split("--")) {
+                let id = id.trim().trim_matches('"');
+                if id.is_empty() {
+                    bail!("Missing node in DOT statement: {stmt:?}");
+                }
+                ids.push(graph.node(id));
+            }
+            for w in ids.windows(2) {
+                graph.edges.push((w[0], w[1]));
+            }
+        }
+
+        Ok(graph)
+    }
+
+    // The index of the named node, adding it if needed.
+    fn node(&mut self, name: &str) -> usize {
+        if let Some(i) = self.nodes.iter().position(|n| n == name) {
+            i
+        } else {
+            self.nodes.push(name.to_string());
+            self.nodes.len() - 1
+        }
+    }
+
+    // Lay out the nodes left to right, joining adjacent nodes directly and
+    // routing other edges in lanes beneath the nodes.
+    pub fn edits(&self, theme: &Theme) -> Vec<Edit> {
+        let line = |start, end| Line {
+            glyphs: theme.line,
+            ..Line::new(start, end)
+        };
+        let mut x = 0;
+        let rects: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|name| {
+                let r = Rect {
+                    glyphs: theme.rect,
+                    ..Rect::new(x, 0, x + name.chars().count() as u16 + 3, 2)
+                };
+                x = r.bottom_right.x + 1 + GAP;
+                r
+            })
+            .collect();
+
+        let mut edits = vec![];
+        for (name, r) in self.nodes.iter().zip(&rects) {
+            edits.extend(r.edits());
+            edits.extend(Text::new(r.top_left.x + 2, 1, name).edits());
+        }
+
+        let mut lane = 4;
+        for &(a, b) in &self.edges {
+            let (ra, rb) = (rects[a], rects[b]);
+            if a == b {
+                log::warn!("Skipping edge from {} to itself", self.nodes[a]);
+            } else if b == a + 1 {
+                let start = UVec {
+                    x: ra.bottom_right.x,
+                    y: 1,
+                };
+                let end = UVec {
+                    x: rb.top_left.x,
+                    y: 1,
+                };
+                edits.extend(line(start, end).edits());
+                if self.directed {
+                    edits.push(Edit::Right {
+                        start: UVec { x: end.x - 1, y: 1 },
+                        chars: vec![theme.arrow.right],
+                    });
+                }
+            } else {
+                let (xa, xb) = (center(&ra), center(&rb));
+                let bend = UVec { x: xb, y: lane };
+                edits.extend(line(UVec { x: xa, y: 2 }, bend).edits());
+                edits.extend(line(bend, UVec { x: xb, y: 2 }).edits());
+                if self.directed {
+                    edits.push(Edit::Down {
+                        start: UVec { x: xb, y: 3 },
+                        chars: vec![theme.arrow.up],
+                    });
+                }
+                lane += 1;
+            }
+        }
+        edits
+    }
+}
+
+fn center(r: &Rect) -> u16 {
+    (r.top_left.x + r.bottom_right.x) / 2
+}
+
+// Lay out a DOT graph on a new canvas.
+pub fn import(s: &str, theme: &Theme) -> Result<Canvas> {
+    let graph = Graph::parse(s)?;
+    log::debug!("Parsed graph: {graph:?}");
+    let mut canvas = Canvas::default();
+    canvas.edit(graph.edits(theme).into_iter());
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse() {
+        let g = Graph::parse(
+            r#"
+            digraph G {
+                rankdir=LR;
+                node [shape=box];
+                a -> b -> "c";
+                b -> a [color=red]
+                d
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            g,
+            Graph {
+                nodes: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+                edges: vec![(0, 1), (1, 2), (1, 0)],
+                directed: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Graph::parse("digraph").is_err());
+        assert!(Graph::parse("digraph { a -> ; }").is_err());
+    }
+
+    #[test]
+    fn test_import_edge() {
+        let canvas = import("digraph { a -> b; }", &Theme::ASCII).unwrap();
+        assert_snapshot!(canvas.to_string());
+    }
+}
@@ -1,7 +1,10 @@
-use crate::vec::UVec;
+use serde::{Deserialize, Serialize};
+
+use crate::vec::{IVec, UVec};
 
 // Edit describes a change applied to a canvas.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
 pub enum Edit {
     Right { start: UVec, chars: Vec<char> },
     Down { start: UVec, chars: Vec<char> },
@@ -25,16 +28,88 @@ impl Edit {
         }
     }
 
-    // Return a version of this edit that erases it's shape.
-    pub fn erase(&self) -> Edit {
+    // The cells written by this edit, in order.
+    pub fn cells(&self) -> impl Iterator<Item = UVec> + '_ {
+        let (start, len, d) = match self {
+            Edit::Right { start, chars } => (*start, chars.len() as u16, UVec { x: 1, y: 0 }),
+            Edit::Down { start, chars } => (*start, chars.len() as u16, UVec { x: 0, y: 1 }),
+        };
+        (0..len).map(move |i| UVec {
+            x: start.x + d.x * i,
+            y: start.y + d.y * i,
+        })
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        match self {
+            Edit::Right { chars, .. } | Edit::Down { chars, .. } => chars.iter().copied(),
+        }
+    }
+
+    // Whether this edit writes to p.
+    pub fn contains(&self, p: UVec) -> bool {
+        let b = self.bounds();
+        match self {
+            Edit::Right { start, .. } => p.y == start.y && p.x >= start.x && p.x < b.x,
+            Edit::Down { start, .. } => p.x == start.x && p.y >= start.y && p.y < b.y,
+        }
+    }
+
+    // Edits writing rows of chars with their top left at start. Spaces are
+    // skipped if transparent, leaving what is under them.
+    pub fn stamp(start: UVec, rows: &[Vec<char>], transparent: bool) -> Vec<Edit> {
+        let mut edits = vec![];
+        for (y, row) in rows.iter().enumerate() {
+            let y = start.y + y as u16;
+            if !transparent {
+                edits.push(Edit::Right {
+                    start: UVec { x: start.x, y },
+                    chars: row.clone(),
+                });
+                continue;
+            }
+            let mut x = 0;
+            for run in row.split(|c| *c == ' ') {
+                if !run.is_empty() {
+                    edits.push(Edit::Right {
+                        start: UVec {
+                            x: start.x + x as u16,
+                            y,
+                        },
+                        chars: run.to_vec(),
+                    });
+                }
+                // +1 for the space that ended the run
+                x += run.len() + 1;
+            }
+        }
+        edits
+    }
+
+    // An edit writing empty over each cell this one writes, in the same
+    // direction, e.g. to remove a shape by erasing the edits that drew it.
+    pub fn erase(&self, empty: char) -> Edit {
         match self {
             Edit::Right { start, chars } => Edit::Right {
                 start: *start,
-                chars: vec![' '; chars.len()],
+                chars: vec![empty; chars.len()],
             },
             Edit::Down { start, chars } => Edit::Down {
                 start: *start,
-                chars: vec![' '; chars.len()],
+                chars: vec![empty; chars.len()],
+            },
+        }
+    }
+
+    pub fn translated(&self, d: IVec) -> Edit {
+        match self {
+            Edit::Right { start, chars } => Edit::Right {
+                start: start.translated(d),
+                chars: chars.clone(),
+            },
+            Edit::Down { start, chars } => Edit::Down {
+                start: start.translated(d),
+                chars: chars.clone(),
             },
         }
     }
@@ -70,6 +145,84 @@ mod tests {
         assert_eq!(actual.bounds(), UVec { x: 4, y: 3 });
     }
 
+    #[test]
+    fn test_edit_stamp() {
+        let rows = vec![vec!['a', ' ', 'b', 'c'], vec![' ', ' ']];
+        let start = UVec { x: 1, y: 2 };
+
+        let cells = |edits: Vec<Edit>| {
+            edits
+                .iter()
+                .flat_map(|e| e.cells().zip(e.chars()).collect::<Vec<_>>())
+                .map(|(p, c)| (p.x, p.y, c))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            cells(Edit::stamp(start, &rows, false)),
+            vec![
+                (1, 2, 'a'),
+                (2, 2, ' '),
+                (3, 2, 'b'),
+                (4, 2, 'c'),
+                (1, 3, ' '),
+                (2, 3, ' ')
+            ]
+        );
+        assert_eq!(
+            cells(Edit::stamp(start, &rows, true)),
+            vec![(1, 2, 'a'), (3, 2, 'b'), (4, 2, 'c')]
+        );
+    }
+
+    #[test]
+    fn test_edit_erase_right() {
+        let e = Edit::Right {
+            start: UVec { x: 1, y: 2 },
+            chars: vec!['a', 'b', 'c'],
+        };
+        let erased = e.erase(' ');
+        assert_eq!(
+            erased,
+            Edit::Right {
+                start: UVec { x: 1, y: 2 },
+                chars: vec![' '; 3],
+            }
+        );
+        assert_eq!(erased.bounds(), e.bounds());
+    }
+
+    #[test]
+    fn test_edit_erase_down() {
+        let e = Edit::Down {
+            start: UVec { x: 4, y: 3 },
+            chars: vec!['a', 'b'],
+        };
+        let erased = e.erase('.');
+        assert_eq!(
+            erased,
+            Edit::Down {
+                start: UVec { x: 4, y: 3 },
+                chars: vec!['.'; 2],
+            }
+        );
+        assert!(erased.cells().eq(e.cells()));
+    }
+
+    #[test]
+    fn test_edit_cells() {
+        let e = Edit::Down {
+            start: UVec { x: 4, y: 3 },
+            chars: vec!['a', 'b'],
+        };
+        assert_eq!(
+            e.cells().collect::<Vec<_>>(),
+            vec![UVec { x: 4, y: 3 }, UVec { x: 4, y: 4 }]
+        );
+        assert!(e.contains(UVec { x: 4, y: 4 }));
+        assert!(!e.contains(UVec { x: 4, y: 5 }));
+        assert!(!e.contains(UVec { x: 3, y: 3 }));
+    }
+
     #[test]
     fn test_edit_bounds_down() {
         let actual = Edit::Down {
@@ -0,0 +1,68 @@
+use std::{io::Read, path::Path};
+
+use anyhow::{Context, Result};
+
+// Read a diagram from path, where `-` is stdin and, when built with the http
+// feature, a path starting with http:// or https:// is fetched.
+pub fn read(path: &Path) -> Result<String> {
+    read_from(path, std::io::stdin().lock())
+}
+
+// Like read, taking stdin from the given reader.
+pub fn read_from(path: &Path, mut stdin: impl Read) -> Result<String> {
+    if path == Path::new("-") {
+        log::debug!("Reading from stdin");
+        let mut s = String::new();
+        stdin
+            .read_to_string(&mut s)
+            .context("Failed to read stdin")?;
+        return Ok(s);
+    }
+    let name = path.to_string_lossy();
+    if name.starts_with("http://") || name.starts_with("https://") {
+        return fetch(&name);
+    }
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))
+}
+
+#[cfg(feature = "http")]
+fn fetch(url: &str) -> Result<String> {
+    log::debug!("Fetching {url}");
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to fetch {url}"))?;
+    Ok(response.into_string()?)
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch(url: &str) -> Result<String> {
+    anyhow::bail!("Cannot fetch {url}: built without the http feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_read_stdin() {
+        let s = read_from(Path::new("-"), "+--+\n+--+\n".as_bytes()).unwrap();
+        assert_eq!(s, "+--+\n+--+\n");
+    }
+
+    #[test]
+    fn test_read_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("-x");
+        std::fs::write(&path, "abc").unwrap();
+        // stdin is only read for a lone `-`
+        assert_eq!(read_from(&path, "stdin".as_bytes()).unwrap(), "abc");
+        assert!(read_from(&dir.path().join("missing"), "".as_bytes()).is_err());
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn test_read_url_without_http() {
+        assert!(read_from(Path::new("https://example.com/a.txt"), "".as_bytes()).is_err());
+    }
+}
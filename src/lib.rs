@@ -1,11 +1,22 @@
 #![feature(array_windows)]
 
+pub mod ansi;
 pub mod binds;
 pub mod canvas;
 pub mod config;
+pub mod dot;
 pub mod edit;
+pub mod input;
 pub mod line;
+pub mod paint;
+pub mod raster;
 pub mod rect;
+pub mod script;
+pub mod session;
+pub mod table;
+#[cfg(test)]
+mod test_log;
 pub mod text;
+pub mod theme;
 pub mod tui;
 pub mod vec;
@@ -1,11 +1,57 @@
+use serde::{Deserialize, Serialize};
+
 use crate::edit::Edit;
-use crate::vec::UVec;
+use crate::text::Text;
+use crate::vec::{IVec, UVec};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weight {
+    #[default]
+    Single,
+    Double,
+}
+
+impl Weight {
+    pub fn toggled(&self) -> Self {
+        match self {
+            Weight::Single => Weight::Double,
+            Weight::Double => Weight::Single,
+        }
+    }
+}
+
+// The characters used to draw a line.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LineGlyphs {
+    pub horizontal: char,
+    pub vertical: char,
+    pub corner: char,
+}
+
+impl LineGlyphs {
+    pub const SINGLE: LineGlyphs = LineGlyphs {
+        horizontal: Line::HORIZONTAL,
+        vertical: Line::VERTICAL,
+        corner: Line::CORNER,
+    };
+
+    pub const DOUBLE: LineGlyphs = LineGlyphs {
+        horizontal: '═',
+        vertical: '║',
+        corner: '╬',
+    };
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Line {
     pub start: UVec,
     pub end: UVec,
     pub mirror: bool,
+    pub glyphs: LineGlyphs,
+    // Draw a straight measurement with ticks at each end and its length in
+    // cells written across the middle.
+    pub dimension: bool,
 }
 
 impl Line {
@@ -18,46 +64,147 @@ impl Line {
             start,
             end,
             mirror: false,
+            glyphs: LineGlyphs::SINGLE,
+            dimension: false,
         }
     }
 
-    fn line(char: char, len: usize) -> Vec<char> {
+    // Constrain point to the nearest horizontal, vertical, or 45 degree
+    // direction from start.
+    pub fn snapped(start: UVec, point: UVec) -> UVec {
+        let (dx, dy) = (point.x.abs_diff(start.x), point.y.abs_diff(start.y));
+        // within ~22 degrees of an axis snaps to that axis
+        if 5 * dy <= 2 * dx {
+            UVec {
+                y: start.y,
+                ..point
+            }
+        } else if 5 * dx <= 2 * dy {
+            UVec {
+                x: start.x,
+                ..point
+            }
+        } else {
+            let sign = |a: u16, b: u16| if a < b { -1 } else { 1 };
+            let (sx, sy) = (sign(point.x, start.x), sign(point.y, start.y));
+            // stop where the first axis hits the edge, to keep the angle at 45°
+            let room = |s: i16, c: u16| if s < 0 { c } else { u16::MAX - c };
+            let d = ((dx + dy) / 2)
+                .min(room(sx, start.x))
+                .min(room(sy, start.y)) as i16;
+            start
+                + IVec {
+                    x: d * sx,
+                    y: d * sy,
+                }
+        }
+    }
+
+    // Whether c is used to draw lines or boxes.
+    pub fn is_line_char(c: char) -> bool {
+        matches!(c, Self::HORIZONTAL | Self::VERTICAL | Self::CORNER)
+            || ('\u{2500}'..='\u{257F}').contains(&c)
+    }
+
+    // The character to draw when `over` is drawn on top of `under`, if the two
+    // cross at a junction of single and double lines.
+    pub fn junction(under: char, over: char) -> Option<char> {
+        match (under, over) {
+            ('|', '═') | ('═', '|') => Some('╪'),
+            ('-', '║') | ('║', '-') => Some('╫'),
+            ('║', '═') | ('═', '║') => Some('╬'),
+            _ => None,
+        }
+    }
+
+    fn line(char: char, corner: char, len: usize) -> Vec<char> {
         let mut chars = vec![char; len + 1];
-        chars[0] = Self::CORNER;
-        chars[len] = Self::CORNER;
+        chars[0] = corner;
+        chars[len] = corner;
         chars
     }
 
-    fn vert(a: UVec, b: UVec) -> Edit {
+    fn vert(a: UVec, b: UVec, g: LineGlyphs) -> Edit {
         let dy = b.y.abs_diff(a.y) as usize;
         Edit::Down {
             start: UVec {
                 x: a.x,
                 y: std::cmp::min(a.y, b.y),
             },
-            chars: Self::line(Self::VERTICAL, dy),
+            chars: Self::line(g.vertical, g.corner, dy),
         }
     }
 
-    fn horiz(a: UVec, b: UVec) -> Edit {
+    fn horiz(a: UVec, b: UVec, g: LineGlyphs) -> Edit {
         let dx = b.x.abs_diff(a.x) as usize;
         Edit::Right {
             start: UVec {
                 x: std::cmp::min(a.x, b.x),
                 y: a.y,
             },
-            chars: Self::line(Self::HORIZONTAL, dx),
+            chars: Self::line(g.horizontal, g.corner, dx),
         }
     }
 
     pub fn edits(&self) -> Vec<Edit> {
         let (a, b) = (self.start, self.end);
+        let g = self.glyphs;
+
+        if self.dimension {
+            self.dimension_edits()
+        } else if self.mirror {
+            vec![
+                Self::horiz(a, b, g),
+                Self::vert(UVec { y: a.y, x: b.x }, b, g),
+            ]
+        } else {
+            vec![
+                Self::vert(a, b, g),
+                Self::horiz(UVec { x: a.x, y: b.y }, b, g),
+            ]
+        }
+    }
+
+    // A straight line along the longer axis from start toward end, with the
+    // ticks across it and the label along it, e.g. |--5--|.
+    fn dimension_edits(&self) -> Vec<Edit> {
+        let (a, g) = (self.start, self.glyphs);
+        let (dx, dy) = (self.end.x.abs_diff(a.x), self.end.y.abs_diff(a.y));
+        let vertical = dy > dx;
+        let len = std::cmp::max(dx, dy) as usize;
+        let label = (len + 1).to_string();
 
-        if self.mirror {
-            vec![Self::horiz(a, b), Self::vert(UVec { y: a.y, x: b.x }, b)]
+        let start = UVec {
+            x: if vertical { a.x } else { a.x.min(self.end.x) },
+            y: if vertical { a.y.min(self.end.y) } else { a.y },
+        };
+        let line = if vertical {
+            Edit::Down {
+                start,
+                chars: Self::line(g.vertical, g.horizontal, len),
+            }
         } else {
-            vec![Self::vert(a, b), Self::horiz(UVec { x: a.x, y: b.y }, b)]
+            Edit::Right {
+                start,
+                chars: Self::line(g.horizontal, g.vertical, len),
+            }
+        };
+
+        // only label a line long enough to keep both ticks
+        let mut edits = vec![line];
+        if label.len() + 2 <= len + 1 {
+            let offset = ((len + 1 - label.len()) / 2) as u16;
+            let text = if vertical {
+                Text {
+                    vertical: true,
+                    ..Text::new(start.x, start.y + offset, &label)
+                }
+            } else {
+                Text::new(start.x + offset, start.y, &label)
+            };
+            edits.extend(text.edits());
         }
+        edits
     }
 }
 
@@ -76,6 +223,18 @@ mod tests {
         assert_eq!(canvas.to_string().trim(), "+")
     }
 
+    #[test]
+    fn test_draw_dimension() {
+        let mut canvas = Canvas::new(8, 3);
+        let r = Line {
+            dimension: true,
+            ..Line::new(UVec { x: 1, y: 1 }, UVec { x: 5, y: 2 })
+        };
+        canvas.edit(r.edits().into_iter());
+        assert_eq!(canvas.rows()[1][3], '5');
+        assert_snapshot!(canvas.to_string())
+    }
+
     #[test]
     fn test_draw_line_down_right() {
         let mut canvas = Canvas::new(8, 8);
@@ -135,6 +294,52 @@ mod tests {
         assert_snapshot!(canvas.to_string())
     }
 
+    #[test]
+    fn test_draw_line_double_crossing() {
+        let mut canvas = Canvas::new(8, 8);
+        let single = Line::new(UVec { x: 3, y: 0 }, UVec { x: 3, y: 4 });
+        canvas.draw(single.edits().into_iter());
+        let double = Line {
+            glyphs: LineGlyphs::DOUBLE,
+            ..Line::new(UVec { x: 0, y: 2 }, UVec { x: 6, y: 2 })
+        };
+        canvas.draw(double.edits().into_iter());
+        assert_snapshot!(canvas.to_string())
+    }
+
+    #[test]
+    fn test_line_snapped() {
+        let start = UVec { x: 4, y: 4 };
+        let snap = |x, y| Line::snapped(start, UVec { x, y });
+        assert_eq!(snap(9, 5), UVec { x: 9, y: 4 });
+        assert_eq!(snap(3, 0), UVec { x: 4, y: 0 });
+        assert_eq!(snap(8, 7), UVec { x: 7, y: 7 });
+        assert_eq!(snap(1, 0), UVec { x: 1, y: 1 });
+        assert_eq!(snap(0, 13), UVec { x: 0, y: 8 });
+        assert_eq!(snap(13, 0), UVec { x: 8, y: 0 });
+        assert_eq!(snap(4, 4), start);
+    }
+
+    #[test]
+    fn test_is_line_char() {
+        for c in ['-', '|', '+', '─', '│', '╭', '╬', '╪'] {
+            assert!(Line::is_line_char(c), "{c}");
+        }
+        for c in [' ', 'a', '*', '>', '_'] {
+            assert!(!Line::is_line_char(c), "{c}");
+        }
+    }
+
+    #[test]
+    fn test_line_junction() {
+        assert_eq!(Line::junction('|', '═'), Some('╪'));
+        assert_eq!(Line::junction('═', '|'), Some('╪'));
+        assert_eq!(Line::junction('-', '║'), Some('╫'));
+        assert_eq!(Line::junction('║', '═'), Some('╬'));
+        assert_eq!(Line::junction('|', '-'), None);
+        assert_eq!(Line::junction(' ', '═'), None);
+    }
+
     #[test]
     fn test_draw_line_down_left_mirror() {
         let mut canvas = Canvas::new(8, 8);
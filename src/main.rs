@@ -1,19 +1,107 @@
+use std::io::Write;
+
 use anyhow::{bail, Result};
-use boxt::{config::Config, tui};
-use clap::Parser;
+use boxt::{binds, canvas::Canvas, config::Config, dot, input, raster, script, tui, vec::UVec};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 pub const APP_NAME: &'static str = env!("CARGO_PKG_NAME");
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct CLI {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // `boxt <path>` is short for `boxt edit <path>`
+    #[command(flatten)]
+    edit: EditArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Open a diagram for editing
+    Edit(EditArgs),
+
+    /// Print a diagram to stdout
+    Render(RenderArgs),
+
+    /// Draw the commands in a script onto a diagram
+    Apply(ApplyArgs),
+
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Args)]
+struct EditArgs {
+    /// Defaults to default_path from the config, or else a scratch canvas
+    path: Option<std::path::PathBuf>,
+
+    /// Where to start the cursor, as x,y
+    #[arg(long)]
+    cursor: Option<UVec>,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// A text diagram, or a graphviz .dot file to lay out. Use - for stdin, or
+    /// with the http feature, an http(s) URL
     path: std::path::PathBuf,
+
+    #[arg(short, long, value_enum, default_value = "text")]
+    format: Format,
+
+    /// After the diagram, list how many times each character is used
+    #[arg(long)]
+    legend: bool,
+
+    /// With png, note the size of a cell in pixels in the bottom right corner
+    #[arg(long)]
+    scale: bool,
+
+    /// Keep only lines and box borders, leaving out labels
+    #[arg(long)]
+    skeleton: bool,
+
+    /// Draw each of the other diagrams to the right of the first
+    #[arg(long)]
+    side_by_side: bool,
+
+    /// More diagrams to draw with --side-by-side
+    #[arg(requires = "side_by_side")]
+    others: Vec<std::path::PathBuf>,
 }
 
-fn main() -> Result<()> {
-    env_logger::init();
-    let args = CLI::parse();
+#[derive(Args)]
+struct ApplyArgs {
+    /// Lines of `rect x1 y1 x2 y2`, `line x1 y1 x2 y2`, or `text x y "..."`
+    script: std::path::PathBuf,
+
+    /// The diagram to draw on, created if it does not exist
+    target: std::path::PathBuf,
+}
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    /// A grid of cells, as {width, height, rows: [[char, ...], ...]}
+    Json,
+    /// An image, with one 8x16 pixel glyph per cell
+    Png,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective configuration as TOML
+    Dump,
+
+    /// Report keys bound more than once and actions with no key
+    Check,
+}
+
+fn read_config() -> Result<Config> {
     let xdg = xdg::BaseDirectories::with_prefix(APP_NAME)?;
 
     let config_path = xdg.get_config_file("config.toml");
@@ -34,6 +122,92 @@ fn main() -> Result<()> {
     };
 
     log::trace!("Using config:\n {config:?}");
+    Ok(config)
+}
+
+// Columns left between diagrams drawn side by side.
+const SIDE_BY_SIDE_GAP: usize = 1;
+
+fn load(path: &std::path::Path, config: &Config) -> Result<Canvas> {
+    let content = input::read(path)?;
+    Ok(match path.extension() {
+        Some(ext) if ext == "dot" => dot::import(&content, &config.theme.theme())?,
+        _ => Canvas::from_str(&content),
+    })
+}
+
+fn render(args: RenderArgs, config: &Config) -> Result<()> {
+    let mut canvas = load(&args.path, config)?;
+    for path in &args.others {
+        canvas = canvas.hconcat(&load(path, config)?, SIDE_BY_SIDE_GAP);
+    }
+    if args.skeleton {
+        canvas = canvas.skeleton();
+    }
+    match args.format {
+        Format::Text | Format::Json if args.scale => bail!("--scale can only be used with png"),
+        Format::Text => println!("{}", canvas.to_string()),
+        Format::Json => println!("{}", canvas.to_json()?),
+        Format::Png if args.legend => bail!("--legend cannot be used with png"),
+        Format::Png => std::io::stdout().write_all(&raster::to_png(&canvas, args.scale)?)?,
+    }
+    if args.legend {
+        println!();
+        for (c, count) in canvas.char_histogram() {
+            println!("{c} {count}");
+        }
+    }
+    Ok(())
+}
 
-    tui::start(config, args.path)
+fn apply(args: ApplyArgs, config: &Config) -> Result<()> {
+    let script = std::fs::read_to_string(&args.script)?;
+    let mut canvas = match std::fs::read_to_string(&args.target) {
+        Ok(content) => Canvas::from_str(&content),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Canvas::default(),
+        Err(err) => bail!("Failed to read {:?}: {err:?}", args.target),
+    };
+    script::apply(&mut canvas, &script, &config.theme.theme())?;
+    std::fs::write(&args.target, canvas.to_string())?;
+    Ok(())
+}
+
+fn check(config: &Config) -> Result<()> {
+    let report = binds::check(config.binds.0.iter().map(|(k, v)| (k.as_str(), v)))?;
+    for keys in &report.conflicts {
+        println!("conflict: {}", keys.join(", "));
+    }
+    for action in &report.unbound {
+        println!("unbound: {action:?}");
+    }
+    if !report.is_ok() {
+        bail!("{} conflicting binds", report.conflicts.len());
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = CLI::parse();
+    let mut config = read_config()?;
+    if std::env::var_os("BOXT_LOG_ACTIONS").is_some() {
+        config.log_actions = true;
+    }
+
+    match args.command.unwrap_or(Command::Edit(args.edit)) {
+        Command::Edit(args) => {
+            let path = args.path.or_else(|| config.default_path.clone());
+            if args.cursor.is_some() {
+                config.cursor = args.cursor;
+            }
+            tui::start(config, path)
+        }
+        Command::Render(args) => render(args, &config),
+        Command::Apply(args) => apply(args, &config),
+        Command::Config(ConfigCommand::Dump) => {
+            print!("{}", config.dump()?);
+            Ok(())
+        }
+        Command::Config(ConfigCommand::Check) => check(&config),
+    }
 }
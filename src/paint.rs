@@ -0,0 +1,69 @@
+use crate::edit::Edit;
+use crate::vec::UVec;
+
+// A freehand stroke of a single character.
+#[derive(Debug, Default)]
+pub struct Paint {
+    // The brush, chosen by the first key pressed after entering paint mode.
+    pub char: Option<char>,
+    pub cells: Vec<UVec>,
+}
+
+impl Paint {
+    // Paint the cell at point, or erase it if it is already painted.
+    pub fn toggle(&mut self, point: UVec) {
+        if let Some(i) = self.cells.iter().position(|p| *p == point) {
+            self.cells.remove(i);
+        } else {
+            self.cells.push(point);
+        }
+    }
+
+    pub fn edits(&self) -> Vec<Edit> {
+        let Some(c) = self.char else {
+            return vec![];
+        };
+        self.cells
+            .iter()
+            .map(|p| Edit::Right {
+                start: *p,
+                chars: vec![c],
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+
+    use super::*;
+
+    #[test]
+    fn test_paint_no_char() {
+        let mut p = Paint::default();
+        p.toggle(UVec { x: 1, y: 1 });
+        assert!(p.edits().is_empty());
+    }
+
+    #[test]
+    fn test_paint_toggle() {
+        let mut canvas = Canvas::new(4, 2);
+        let mut p = Paint {
+            char: Some('*'),
+            ..Default::default()
+        };
+        for x in 0..4 {
+            p.toggle(UVec { x, y: 0 });
+        }
+        p.toggle(UVec { x: 3, y: 0 });
+        p.toggle(UVec { x: 2, y: 1 });
+        canvas.edit(p.edits().into_iter());
+        assert_eq!(
+            canvas.to_string(),
+            "\
+*** 
+  * "
+        )
+    }
+}
@@ -0,0 +1,167 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use font8x8::legacy::BASIC_LEGACY;
+use image::{GrayImage, ImageFormat, Luma};
+
+use crate::{canvas::Canvas, vec::UVec};
+
+// The size in pixels of each cell. Font glyphs are 8x8, drawn at double
+// height to keep the proportions of a terminal cell.
+pub const CELL_WIDTH: u32 = 8;
+pub const CELL_HEIGHT: u32 = 16;
+
+const INK: Luma<u8> = Luma([0]);
+const PAPER: Luma<u8> = Luma([255]);
+
+// How a box drawing character extends from the center of its cell, as the
+// number of strokes in each direction: up, down, left, right.
+fn arms(c: char) -> Option<[u8; 4]> {
+    Some(match c {
+        '─' => [0, 0, 1, 1],
+        '│' => [1, 1, 0, 0],
+        '┌' | '╭' => [0, 1, 0, 1],
+        '┐' | '╮' => [0, 1, 1, 0],
+        '└' | '╰' => [1, 0, 0, 1],
+        '┘' | '╯' => [1, 0, 1, 0],
+        '├' => [1, 1, 0, 1],
+        '┤' => [1, 1, 1, 0],
+        '┬' => [0, 1, 1, 1],
+        '┴' => [1, 0, 1, 1],
+        '┼' => [1, 1, 1, 1],
+        '═' => [0, 0, 2, 2],
+        '║' => [2, 2, 0, 0],
+        '╔' => [0, 2, 0, 2],
+        '╗' => [0, 2, 2, 0],
+        '╚' => [2, 0, 0, 2],
+        '╝' => [2, 0, 2, 0],
+        '╪' => [1, 1, 2, 2],
+        '╫' => [2, 2, 1, 1],
+        '╬' => [2, 2, 2, 2],
+        _ => return None,
+    })
+}
+
+fn draw_arms(img: &mut GrayImage, x0: u32, y0: u32, [up, down, left, right]: [u8; 4]) {
+    let (cx, cy) = (x0 + CELL_WIDTH / 2, y0 + CELL_HEIGHT / 2);
+    // a double stroke is drawn as two lines either side of the center
+    let offsets = |n| match n {
+        1 => vec![0],
+        2 => vec![-1, 1],
+        _ => vec![],
+    };
+    for d in offsets(up) {
+        (y0..=cy).for_each(|y| img.put_pixel(cx.saturating_add_signed(d), y, INK));
+    }
+    for d in offsets(down) {
+        (cy..y0 + CELL_HEIGHT).for_each(|y| img.put_pixel(cx.saturating_add_signed(d), y, INK));
+    }
+    for d in offsets(left) {
+        (x0..=cx).for_each(|x| img.put_pixel(x, cy.saturating_add_signed(d), INK));
+    }
+    for d in offsets(right) {
+        (cx..x0 + CELL_WIDTH).for_each(|x| img.put_pixel(x, cy.saturating_add_signed(d), INK));
+    }
+}
+
+fn draw_glyph(img: &mut GrayImage, x0: u32, y0: u32, c: char) {
+    let c = if c.is_ascii() { c } else { '?' };
+    for (y, row) in BASIC_LEGACY[c as usize].iter().enumerate() {
+        for x in (0..8).filter(|x| row & (1 << x) != 0) {
+            let y = y0 + y as u32 * 2;
+            img.put_pixel(x0 + x, y, INK);
+            img.put_pixel(x0 + x, y + 1, INK);
+        }
+    }
+}
+
+// Draw each cell of the canvas, with box drawing characters drawn as lines
+// that meet those of neighbouring cells.
+pub fn rasterize(canvas: &Canvas) -> GrayImage {
+    let dim = canvas.dimensions();
+    let mut img =
+        GrayImage::from_pixel(dim.x as u32 * CELL_WIDTH, dim.y as u32 * CELL_HEIGHT, PAPER);
+    for (y, row) in canvas.rows().iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            let (x0, y0) = (x as u32 * CELL_WIDTH, y as u32 * CELL_HEIGHT);
+            match arms(*c) {
+                Some(a) => draw_arms(&mut img, x0, y0, a),
+                None if *c == ' ' => {}
+                None => draw_glyph(&mut img, x0, y0, *c),
+            }
+        }
+    }
+    img
+}
+
+// A note of how large each cell is drawn.
+pub fn scale_label() -> String {
+    format!("1 cell = {CELL_WIDTH}x{CELL_HEIGHT}px")
+}
+
+// A copy of canvas with the scale label on a row of its own, in the bottom
+// right corner.
+fn with_scale(canvas: &Canvas) -> Canvas {
+    let mut canvas = canvas.clone();
+    let label = Canvas::from_str(&scale_label());
+    let dim = canvas.dimensions();
+    let at = UVec {
+        x: dim.x.saturating_sub(label.dimensions().x),
+        y: dim.y,
+    };
+    canvas.overlay(&label, at, true);
+    canvas
+}
+
+// Encode the canvas as a png, with a scale label if scale is set.
+pub fn to_png(canvas: &Canvas, scale: bool) -> Result<Vec<u8>> {
+    let scaled;
+    let canvas = if scale {
+        scaled = with_scale(canvas);
+        &scaled
+    } else {
+        canvas
+    };
+    let mut bytes = vec![];
+    rasterize(canvas).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_png() {
+        let canvas = Canvas::from_str("┌─┐ ab\n└─┘ cd");
+        let png = to_png(&canvas, false).unwrap();
+        let img = image::load_from_memory(&png).unwrap();
+        assert_eq!(img.width(), 6 * CELL_WIDTH);
+        assert_eq!(img.height(), 2 * CELL_HEIGHT);
+    }
+
+    #[test]
+    fn test_to_png_scale() {
+        let canvas = Canvas::from_str("┌─┐ ab\n└─┘ cd");
+        let scaled = with_scale(&canvas);
+        assert_eq!(scaled.to_string().lines().last(), Some("1 cell = 8x16px"));
+
+        let png = to_png(&canvas, true).unwrap();
+        let img = image::load_from_memory(&png).unwrap();
+        assert_eq!(img.width(), 15 * CELL_WIDTH);
+        assert_eq!(img.height(), 3 * CELL_HEIGHT);
+    }
+
+    #[test]
+    fn test_rasterize_lines() {
+        let img = rasterize(&Canvas::from_str("─│"));
+        let cy = CELL_HEIGHT / 2;
+        // the horizontal line spans its cell
+        assert!((0..CELL_WIDTH).all(|x| img[(x, cy)] == INK));
+        // the vertical line spans its cell, and nothing else is drawn beside it
+        let cx = CELL_WIDTH + CELL_WIDTH / 2;
+        assert!((0..CELL_HEIGHT).all(|y| img[(cx, y)] == INK));
+        assert_eq!(img[(cx + 1, 0)], PAPER);
+    }
+}
@@ -1,35 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::canvas::Canvas;
 use crate::edit::Edit;
+use crate::line::Weight;
 use crate::vec::{IVec, UVec};
 
+// The characters used to draw the border of a rect.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RectGlyphs {
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    pub horizontal: char,
+    pub vertical: char,
+}
+
+impl RectGlyphs {
+    // +---+
+    // |   |
+    // +---+
+    pub const ASCII: RectGlyphs = RectGlyphs {
+        top_left: '+',
+        top_right: '+',
+        bottom_left: '+',
+        bottom_right: '+',
+        horizontal: '-',
+        vertical: '|',
+    };
+
+    // ┌───┐
+    // │   │
+    // └───┘
+    pub const SQUARE: RectGlyphs = RectGlyphs {
+        top_left: '┌',
+        top_right: '┐',
+        bottom_left: '└',
+        bottom_right: '┘',
+        horizontal: '─',
+        vertical: '│',
+    };
+
+    // ╭───╮
+    // │   │
+    // ╰───╯
+    pub const ROUNDED: RectGlyphs = RectGlyphs {
+        top_left: '╭',
+        top_right: '╮',
+        bottom_left: '╰',
+        bottom_right: '╯',
+        horizontal: '─',
+        vertical: '│',
+    };
+
+    // ┌┄┄┄┐
+    // ┆   ┆
+    // └┄┄┄┘
+    pub const DASHED: RectGlyphs = RectGlyphs {
+        top_left: '┌',
+        top_right: '┐',
+        bottom_left: '└',
+        bottom_right: '┘',
+        horizontal: '┄',
+        vertical: '┆',
+    };
+
+    // ╔═══╗
+    // ║   ║
+    // ╚═══╝
+    pub const DOUBLE: RectGlyphs = RectGlyphs {
+        top_left: '╔',
+        top_right: '╗',
+        bottom_left: '╚',
+        bottom_right: '╝',
+        horizontal: '═',
+        vertical: '║',
+    };
+
+    // Where a divider meets the left and right sides. Tees are only known for
+    // unicode borders, others reuse a corner.
+    pub fn tees(&self) -> (char, char) {
+        match self.horizontal {
+            '─' => ('├', '┤'),
+            '═' => ('╠', '╣'),
+            _ => (self.top_left, self.top_right),
+        }
+    }
+
+    pub fn corners(&self) -> [char; 4] {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_left,
+            self.bottom_right,
+        ]
+    }
+}
+
+// Drawn one cell down and right of a rect with a shadow.
+pub const SHADE: char = '░';
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RectStyle {
+    #[default]
+    Square,
+    Rounded,
+    Dashed,
+}
+
+impl RectStyle {
+    pub const ALL: [RectStyle; 3] = [RectStyle::Square, RectStyle::Rounded, RectStyle::Dashed];
+
+    pub fn glyphs(&self, weight: Weight) -> RectGlyphs {
+        match (self, weight) {
+            // there are no rounded double corners
+            (_, Weight::Double) => RectGlyphs::DOUBLE,
+            (RectStyle::Square, Weight::Single) => RectGlyphs::ASCII,
+            (RectStyle::Rounded, Weight::Single) => RectGlyphs::ROUNDED,
+            (RectStyle::Dashed, Weight::Single) => RectGlyphs::DASHED,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct Rect {
     pub top_left: UVec,
     pub bottom_right: UVec,
+    pub glyphs: RectGlyphs,
+    pub shadow: bool,
 }
 
 impl Rect {
-    // ┌───┐
-    // │   │
-    // └───┘
-
-    pub const TOP_LEFT: char = '+';
-    pub const TOP_RIGHT: char = '+';
-    pub const HORIZONTAL: char = '-';
-    pub const VERTICAL: char = '|';
-    pub const BOTTOM_LEFT: char = '+';
-    pub const BOTTOM_RIGHT: char = '+';
-    pub const CORNERS: [char; 4] = [
-        Self::TOP_LEFT,
-        Self::TOP_RIGHT,
-        Self::BOTTOM_LEFT,
-        Self::BOTTOM_RIGHT,
-    ];
-
     pub fn new(x1: u16, y1: u16, x2: u16, y2: u16) -> Rect {
         Self {
             top_left: UVec { x: x1, y: y1 },
             bottom_right: UVec { x: x2, y: y2 },
+            glyphs: RectGlyphs::ASCII,
+            shadow: false,
         }
     }
 
@@ -37,6 +146,7 @@ impl Rect {
         Self {
             top_left: self.top_left.translated(d),
             bottom_right: self.bottom_right.translated(d),
+            ..*self
         }
     }
 
@@ -44,6 +154,8 @@ impl Rect {
         let Rect {
             top_left: UVec { x: x1, y: y1 },
             bottom_right: UVec { x: x2, y: y2 },
+            glyphs: g,
+            ..
         } = *self;
 
         let (x1, x2) = if x1 < x2 { (x1, x2) } else { (x2, x1) };
@@ -55,15 +167,15 @@ impl Rect {
         let w = (x2 - x1) as usize;
         let h = (y2 - y1) as usize;
 
-        let mut top = vec![Self::HORIZONTAL; w + 1];
-        top[0] = Self::TOP_LEFT;
-        top[w] = Self::TOP_RIGHT;
+        let mut top = vec![g.horizontal; w + 1];
+        top[0] = g.top_left;
+        top[w] = g.top_right;
 
-        let mut bottom = vec![Self::HORIZONTAL; w + 1];
-        bottom[0] = Self::BOTTOM_LEFT;
-        bottom[w] = Self::BOTTOM_RIGHT;
+        let mut bottom = vec![g.horizontal; w + 1];
+        bottom[0] = g.bottom_left;
+        bottom[w] = g.bottom_right;
 
-        let side = vec![Self::VERTICAL; h.saturating_sub(1)];
+        let side = vec![g.vertical; h.saturating_sub(1)];
 
         vec![
             Edit::Right {
@@ -84,6 +196,91 @@ impl Rect {
             },
         ]
     }
+
+    // The shadow down and right of the rect, if it has one, to draw after the
+    // rect. Each side stops at the first cell that is not empty, so it never
+    // covers another shape or runs on into the inside of a box.
+    pub fn shadow_edits(&self, canvas: &Canvas) -> Vec<Edit> {
+        if !self.shadow {
+            return vec![];
+        }
+        let (x1, x2) = (
+            self.top_left.x.min(self.bottom_right.x),
+            self.top_left.x.max(self.bottom_right.x),
+        );
+        let (y1, y2) = (
+            self.top_left.y.min(self.bottom_right.y),
+            self.top_left.y.max(self.bottom_right.y),
+        );
+        let rows = canvas.rows();
+        let empty = |x: u16, y: u16| {
+            rows.get(y as usize)
+                .and_then(|row| row.get(x as usize))
+                .map_or(true, |c| *c == ' ')
+        };
+        let down = (y1 + 1..=y2 + 1).take_while(|y| empty(x2 + 1, *y)).count();
+        let right = (x1 + 1..=x2).take_while(|x| empty(*x, y2 + 1)).count();
+        [
+            Edit::Down {
+                start: UVec {
+                    x: x2 + 1,
+                    y: y1 + 1,
+                },
+                chars: vec![SHADE; down],
+            },
+            Edit::Right {
+                start: UVec {
+                    x: x1 + 1,
+                    y: y2 + 1,
+                },
+                chars: vec![SHADE; right],
+            },
+        ]
+        .into_iter()
+        .filter(|e| e.chars().next().is_some())
+        .collect()
+    }
+
+    // Like edits, but drawn as a window with title in a bar across the top,
+    // divided from the rest. Titles too long for the bar are cut short.
+    pub fn window_edits(&self, title: &str) -> Vec<Edit> {
+        let mut edits = self.edits();
+        let g = self.glyphs;
+        let (x1, x2) = (
+            self.top_left.x.min(self.bottom_right.x),
+            self.top_left.x.max(self.bottom_right.x),
+        );
+        let (y1, y2) = (
+            self.top_left.y.min(self.bottom_right.y),
+            self.top_left.y.max(self.bottom_right.y),
+        );
+        if y2 - y1 < 2 || x2 - x1 < 2 {
+            log::debug!("No room for a title in {self:?}");
+            return edits;
+        }
+
+        let w = (x2 - x1) as usize;
+        edits.push(Edit::Right {
+            start: UVec {
+                x: x1 + 1,
+                y: y1 + 1,
+            },
+            chars: title.chars().take(w - 1).collect(),
+        });
+        // divide the title from the body only if the body keeps a row, as the
+        // bottom border already closes a title with nothing below it
+        if y2 - y1 > 3 {
+            let (left, right) = g.tees();
+            let mut divider = vec![g.horizontal; w + 1];
+            divider[0] = left;
+            divider[w] = right;
+            edits.push(Edit::Right {
+                start: UVec { x: x1, y: y1 + 2 },
+                chars: divider,
+            });
+        }
+        edits
+    }
 }
 
 #[cfg(test)]
@@ -91,6 +288,7 @@ mod tests {
     use crate::canvas::Canvas;
 
     use super::*;
+    use insta::assert_snapshot;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -170,6 +368,65 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_draw_rect_rounded() {
+        let mut canvas = Canvas::new(5, 3);
+        let r = Rect {
+            glyphs: RectStyle::Rounded.glyphs(Weight::Single),
+            ..Rect::new(0, 0, 4, 2)
+        };
+        canvas.edit(r.edits().into_iter());
+        assert_eq!(
+            canvas.to_string().trim(),
+            "\
+╭───╮
+│   │
+╰───╯"
+        )
+    }
+
+    #[test]
+    fn test_draw_rect_double() {
+        let mut canvas = Canvas::new(5, 3);
+        let r = Rect {
+            glyphs: RectStyle::Square.glyphs(Weight::Double),
+            ..Rect::new(0, 0, 4, 2)
+        };
+        canvas.edit(r.edits().into_iter());
+        assert_snapshot!(canvas.to_string())
+    }
+
+    #[test]
+    fn test_draw_window() {
+        let mut canvas = Canvas::default();
+        canvas.edit(Rect::new(0, 0, 9, 4).window_edits("Settings").into_iter());
+        let r = Rect {
+            glyphs: RectGlyphs::SQUARE,
+            ..Rect::new(11, 0, 16, 4)
+        };
+        canvas.edit(r.window_edits("Too long").into_iter());
+        assert_snapshot!(canvas.to_string());
+    }
+
+    #[test]
+    fn test_draw_rect_shadow() {
+        let mut canvas = Canvas::new(9, 5);
+        canvas.draw(Rect::new(5, 2, 8, 4).edits().into_iter());
+        let r = Rect {
+            shadow: true,
+            ..Rect::new(0, 0, 5, 2)
+        };
+        let shadow = r.shadow_edits(&canvas);
+        canvas.draw(r.edits().into_iter().chain(shadow));
+        assert_snapshot!(canvas.to_string());
+
+        canvas.undo();
+        assert_eq!(
+            canvas.to_string(),
+            "         \n         \n     +--+\n     |  |\n     +--+"
+        );
+    }
+
     #[test]
     fn test_rect_translated() {
         let r = Rect::new(4, 2, 8, 5);
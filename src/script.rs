@@ -0,0 +1,129 @@
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    canvas::Canvas, edit::Edit, line::Line, rect::Rect, text::Text, theme::Theme, vec::UVec,
+};
+
+// Parse a script of drawing commands, one per line:
+//
+//   rect x1 y1 x2 y2
+//   line x1 y1 x2 y2
+//   text x y "..."
+//
+// Blank lines and lines starting with '#' are skipped.
+pub fn parse(s: &str, theme: &Theme) -> Result<Vec<Edit>> {
+    let mut edits = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let cmd = parse_line(line, theme).with_context(|| format!("Line {}: {line}", i + 1))?;
+        edits.extend(cmd);
+    }
+    Ok(edits)
+}
+
+fn parse_line(line: &str, theme: &Theme) -> Result<Vec<Edit>> {
+    let (cmd, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    match cmd {
+        "rect" => {
+            let [x1, y1, x2, y2] = numbers(args)?;
+            Ok(Rect {
+                glyphs: theme.rect,
+                ..Rect::new(x1, y1, x2, y2)
+            }
+            .edits())
+        }
+        "line" => {
+            let [x1, y1, x2, y2] = numbers(args)?;
+            Ok(Line {
+                glyphs: theme.line,
+                ..Line::new(UVec { x: x1, y: y1 }, UVec { x: x2, y: y2 })
+            }
+            .edits())
+        }
+        "text" => {
+            let mut parts = args.trim().splitn(3, char::is_whitespace);
+            let (Some(x), Some(y), Some(text)) = (parts.next(), parts.next(), parts.next()) else {
+                bail!("Expected text x y \"...\"");
+            };
+            Ok(Text::new(x.parse()?, y.parse()?, &quoted(text.trim())?).edits())
+        }
+        _ => bail!("Unknown command: {cmd}"),
+    }
+}
+
+fn numbers<const N: usize>(args: &str) -> Result<[u16; N]> {
+    let nums = args
+        .split_whitespace()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<u16>, _>>()?;
+    let n = nums.len();
+    nums.try_into()
+        .map_err(|_| anyhow::anyhow!("Expected {N} numbers, got {n}"))
+}
+
+// Strip the quotes from a string, unescaping \", \\, and \n.
+fn quoted(s: &str) -> Result<String> {
+    let Some(inner) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) else {
+        bail!("Expected a quoted string: {s}");
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next() {
+            Some('n') => '\n',
+            Some(c @ ('"' | '\\')) => c,
+            other => bail!("Unknown escape: \\{}", other.unwrap_or_default()),
+        });
+    }
+    Ok(out)
+}
+
+// Draw the commands in a script onto canvas, as a single undoable edit.
+pub fn apply(canvas: &mut Canvas, s: &str, theme: &Theme) -> Result<()> {
+    let edits = parse(s, theme)?;
+    log::debug!("Applying {} edits from script", edits.len());
+    canvas.edit(edits.into_iter());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_apply() {
+        let script = r#"
+            # a box with a label, and a line leading from it
+            rect 0 0 8 2
+            text 2 1 "hello"
+            line 4 2 12 5
+            text 6 4 "say \"hi\""
+        "#;
+        let mut canvas = Canvas::default();
+        apply(&mut canvas, script, &Theme::ASCII).unwrap();
+        assert_snapshot!(canvas.to_string());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in [
+            "circle 1 2 3",
+            "rect 1 2 3",
+            "rect 1 2 3 -4",
+            "line a b c d",
+            "text 1 2 hello",
+            "text 1 2",
+            "text 1 2 \"\\q\"",
+        ] {
+            assert!(parse(s, &Theme::ASCII).is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{canvas::History, vec::UVec};
+
+// Editor state kept beside a diagram between runs, so work can resume where
+// it was left.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    pub cursor: UVec,
+    // The top left of the visible part of the canvas.
+    pub viewport: UVec,
+}
+
+impl Session {
+    // The sidecar for `dir/name` is `dir/.name.boxt-session`.
+    pub fn path(file: &Path) -> PathBuf {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        file.with_file_name(format!(".{name}.boxt-session"))
+    }
+
+    // Read the session for file, or the default if there is none.
+    pub fn load(file: &Path) -> Session {
+        let path = Self::path(file);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(err) => {
+                log::debug!("No session at {path:?}: {err}");
+                return Session::default();
+            }
+        };
+        toml::from_str(&content).unwrap_or_else(|err| {
+            log::warn!("Ignoring invalid session at {path:?}: {err}");
+            Session::default()
+        })
+    }
+
+    pub fn save(&self, file: &Path) -> Result<()> {
+        let path = Self::path(file);
+        log::debug!("Saving session to {path:?}");
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+// The undo history for `dir/name` is kept in `dir/.name.boxt-history`.
+pub fn history_path(file: &Path) -> PathBuf {
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    file.with_file_name(format!(".{name}.boxt-history"))
+}
+
+pub fn load_history(file: &Path) -> Option<History> {
+    let path = history_path(file);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(err) => {
+            log::debug!("No undo history at {path:?}: {err}");
+            return None;
+        }
+    };
+    serde_json::from_str(&content)
+        .inspect_err(|err| log::warn!("Ignoring invalid undo history at {path:?}: {err}"))
+        .ok()
+}
+
+pub fn save_history(history: &History, file: &Path) -> Result<()> {
+    let path = history_path(file);
+    log::debug!("Saving undo history to {path:?}");
+    std::fs::write(path, serde_json::to_string(history)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_session_path() {
+        assert_eq!(
+            Session::path(Path::new("dir/foo.txt")),
+            PathBuf::from("dir/.foo.txt.boxt-session")
+        );
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("diagram.txt");
+        assert_eq!(Session::load(&file), Session::default());
+
+        let session = Session {
+            cursor: UVec { x: 3, y: 5 },
+            viewport: UVec { x: 1, y: 2 },
+        };
+        session.save(&file).unwrap();
+        assert_eq!(Session::load(&file), session);
+
+        std::fs::write(Session::path(&file), "cursor = 4").unwrap();
+        assert_eq!(Session::load(&file), Session::default());
+    }
+}
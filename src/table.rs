@@ -0,0 +1,148 @@
+use crate::edit::Edit;
+use crate::rect::RectGlyphs;
+use crate::vec::UVec;
+
+// A grid of cells, where neighbouring cells share a border.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct Table {
+    pub origin: UVec,
+    pub rows: u16,
+    pub cols: u16,
+    // The size of the space inside each cell, not counting borders.
+    pub cell_w: u16,
+    pub cell_h: u16,
+    pub glyphs: RectGlyphs,
+}
+
+impl Table {
+    pub fn new(origin: UVec, rows: u16, cols: u16, cell_w: u16, cell_h: u16) -> Table {
+        Self {
+            origin,
+            rows,
+            cols,
+            cell_w,
+            cell_h,
+            glyphs: RectGlyphs::ASCII,
+        }
+    }
+
+    // Where borders meet, by whether there is a border above, below, left,
+    // and right.
+    fn junction(&self, up: bool, down: bool, left: bool, right: bool) -> char {
+        let g = self.glyphs;
+        // tees are only known for unicode borders, others reuse a corner
+        let [tee_down, tee_up, tee_right, tee_left, cross] = match g.horizontal {
+            '─' => ['┬', '┴', '├', '┤', '┼'],
+            '═' => ['╦', '╩', '╠', '╣', '╬'],
+            _ => [g.top_left; 5],
+        };
+        match (up, down, left, right) {
+            (false, true, false, true) => g.top_left,
+            (false, true, true, false) => g.top_right,
+            (true, false, false, true) => g.bottom_left,
+            (true, false, true, false) => g.bottom_right,
+            (false, true, true, true) => tee_down,
+            (true, false, true, true) => tee_up,
+            (true, true, false, true) => tee_right,
+            (true, true, true, false) => tee_left,
+            _ => cross,
+        }
+    }
+
+    // The size of canvas needed to hold the table, like Edit::bounds, or None
+    // if it reaches past the largest coordinate.
+    pub fn bounds(&self) -> Option<UVec> {
+        let (rows, cols) = (self.rows.max(1), self.cols.max(1));
+        let width = cols
+            .checked_mul(self.cell_w.checked_add(1)?)?
+            .checked_add(1)?;
+        let height = rows
+            .checked_mul(self.cell_h.checked_add(1)?)?
+            .checked_add(1)?;
+        Some(UVec {
+            x: self.origin.x.checked_add(width)?,
+            y: self.origin.y.checked_add(height)?,
+        })
+    }
+
+    pub fn edits(&self) -> Vec<Edit> {
+        if self.bounds().is_none() {
+            log::warn!("Table is too big to draw: {self:?}");
+            return vec![];
+        }
+        let g = self.glyphs;
+        let (rows, cols) = (self.rows.max(1), self.cols.max(1));
+        let (step_x, step_y) = (self.cell_w + 1, self.cell_h + 1);
+        let width = (cols * step_x) as usize + 1;
+
+        // a full row for each horizontal border, with junctions at each column
+        let mut edits: Vec<Edit> = (0..=rows)
+            .map(|r| {
+                let mut chars = vec![g.horizontal; width];
+                for c in 0..=cols {
+                    chars[(c * step_x) as usize] = self.junction(r > 0, r < rows, c > 0, c < cols);
+                }
+                Edit::Right {
+                    start: UVec {
+                        x: self.origin.x,
+                        y: self.origin.y + r * step_y,
+                    },
+                    chars,
+                }
+            })
+            .collect();
+
+        // the vertical borders between them
+        for r in 0..rows {
+            for c in 0..=cols {
+                edits.push(Edit::Down {
+                    start: UVec {
+                        x: self.origin.x + c * step_x,
+                        y: self.origin.y + r * step_y + 1,
+                    },
+                    chars: vec![g.vertical; self.cell_h as usize],
+                });
+            }
+        }
+
+        edits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_draw_table() {
+        let mut canvas = Canvas::default();
+        let table = Table::new(UVec { x: 1, y: 1 }, 2, 2, 3, 1);
+        canvas.edit(table.edits().into_iter());
+        assert_snapshot!(canvas.to_string());
+    }
+
+    #[test]
+    fn test_table_bounds() {
+        let table = Table::new(UVec { x: 1, y: 1 }, 2, 2, 3, 1);
+        assert_eq!(table.bounds(), Some(UVec { x: 10, y: 6 }));
+
+        let table = Table::new(UVec { x: 1, y: 1 }, 2, 3, 40000, 1);
+        assert_eq!(table.bounds(), None);
+        assert!(table.edits().is_empty());
+    }
+
+    #[test]
+    fn test_draw_table_unicode() {
+        let mut canvas = Canvas::default();
+        let table = Table {
+            glyphs: RectGlyphs::SQUARE,
+            ..Table::new(UVec::default(), 2, 3, 1, 1)
+        };
+        canvas.edit(table.edits().into_iter());
+        assert_snapshot!(canvas.to_string());
+    }
+}
@@ -0,0 +1,49 @@
+use std::cell::RefCell;
+
+thread_local! {
+    // (target, message) for each record logged on this thread.
+    static CAPTURED: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+// Writes through env_logger as usual, and also keeps what was logged on each
+// thread, so a test can read back its own lines while others run alongside.
+struct TestLogger(env_logger::Logger);
+
+impl log::Log for TestLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        CAPTURED.with(|c| {
+            c.borrow_mut()
+                .push((record.target().to_string(), record.args().to_string()))
+        });
+        self.0.log(record);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+// Install the logger for all tests. Safe to call from every test that logs.
+pub fn init() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let inner = env_logger::builder().is_test(true).build();
+        log::set_max_level(std::cmp::max(log::LevelFilter::Info, inner.filter()));
+        log::set_logger(Box::leak(Box::new(TestLogger(inner)))).expect("logger already set");
+    });
+}
+
+// Take the messages logged to target on this thread since the last take.
+pub fn take(target: &str) -> Vec<String> {
+    CAPTURED.with(|c| {
+        c.take()
+            .into_iter()
+            .filter(|(t, _)| t == target)
+            .map(|(_, msg)| msg)
+            .collect()
+    })
+}
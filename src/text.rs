@@ -1,10 +1,15 @@
 use crate::edit::Edit;
+use crate::theme::Theme;
 use crate::vec::UVec;
 
 #[derive(Debug)]
 pub struct Text {
     pub start: UVec,
     pub text: String,
+    // Write each line top to bottom, with lines running left to right.
+    pub vertical: bool,
+    // Wrap lines longer than this at spaces.
+    pub wrap_width: Option<u16>,
 }
 
 impl Text {
@@ -12,22 +17,119 @@ impl Text {
         Self {
             start: UVec { x, y },
             text: text.into(),
+            vertical: false,
+            wrap_width: None,
+        }
+    }
+
+    // The lines to draw, after wrapping.
+    fn lines(&self) -> Vec<String> {
+        let Some(width) = self.wrap_width else {
+            return self.text.lines().map(String::from).collect();
+        };
+        let width = width as usize;
+        let mut lines = vec![];
+        for line in self.text.lines() {
+            let mut rest: Vec<char> = line.chars().collect();
+            while rest.len() > width {
+                // break at the last space that fits, or else the first after,
+                // so a word longer than the width gets a line to itself, but
+                // never within the indentation
+                let indent = rest.iter().take_while(|c| **c == ' ').count();
+                let space = |i: &usize| rest[*i] == ' ';
+                let fits = (indent + 1..=width).rev().find(space);
+                let Some(i) = fits.or_else(|| (width.max(indent) + 1..rest.len()).find(space))
+                else {
+                    break;
+                };
+                lines.push(rest[..i].iter().collect());
+                rest.drain(..=i);
+            }
+            lines.push(rest.into_iter().collect());
+        }
+        lines
+    }
+
+    // Where the next char typed goes, after wrapping.
+    pub fn end(&self) -> UVec {
+        let lines = self.lines();
+        let (row, col) = match lines.last() {
+            Some(last) if !self.text.ends_with('\n') => (lines.len() - 1, last.chars().count()),
+            _ => (lines.len(), 0),
+        };
+        let (x, y) = if self.vertical {
+            (row, col)
+        } else {
+            (col, row)
+        };
+        UVec {
+            x: self.start.x + x as u16,
+            y: self.start.y + y as u16,
         }
     }
 
     pub fn edits(&self) -> Vec<Edit> {
-        self.text
-            .lines()
+        self.lines()
+            .iter()
             .enumerate()
-            .map(|(i, line)| Edit::Right {
-                start: UVec {
-                    x: self.start.x,
-                    y: self.start.y + i as u16,
-                },
-                chars: line.chars().collect(),
+            .map(|(i, line)| {
+                let chars = line.chars().collect();
+                if self.vertical {
+                    Edit::Down {
+                        start: UVec {
+                            x: self.start.x + i as u16,
+                            y: self.start.y,
+                        },
+                        chars,
+                    }
+                } else {
+                    Edit::Right {
+                        start: UVec {
+                            x: self.start.x,
+                            y: self.start.y + i as u16,
+                        },
+                        chars,
+                    }
+                }
             })
             .collect()
     }
+
+    // Like edits, but arrows typed as `-->` or `<--` are drawn with the line
+    // and arrow glyphs of theme.
+    pub fn arrow_edits(&self, theme: &Theme) -> Vec<Edit> {
+        let mut edits = self.edits();
+        for e in &mut edits {
+            if let Edit::Right { chars, .. } = e {
+                convert_arrows(chars, theme);
+            }
+        }
+        edits
+    }
+}
+
+// Replace each run of two or more '-' with a '<' before or '>' after it.
+fn convert_arrows(chars: &mut [char], theme: &Theme) {
+    let mut i = 0;
+    while i < chars.len() {
+        let left = chars[i] == '<';
+        let start = if left { i + 1 } else { i };
+        let end = start + chars[start..].iter().take_while(|c| **c == '-').count();
+        let right = chars.get(end) == Some(&'>');
+        if end - start < 2 || !(left || right) {
+            i += 1;
+            continue;
+        }
+        log::debug!("Converting arrow at {i}..={end}");
+        chars[start..end].fill(theme.line.horizontal);
+        if left {
+            chars[i] = theme.arrow.left;
+        }
+        if right {
+            chars[end] = theme.arrow.right;
+        }
+        i = end + 1;
+    }
 }
 
 #[cfg(test)]
@@ -35,6 +137,7 @@ mod tests {
     use crate::canvas::Canvas;
 
     use super::*;
+    use insta::assert_snapshot;
 
     #[test]
     fn test_draw_text_empty() {
@@ -56,6 +159,55 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_draw_text_vertical() {
+        let mut canvas = Canvas::new(0, 0);
+        let t = Text {
+            vertical: true,
+            ..Text::new(1, 0, "ABC")
+        };
+        canvas.edit(t.edits().into_iter());
+        assert_eq!(canvas.dimensions(), UVec { x: 2, y: 3 });
+        assert_eq!(canvas.to_string(), " A\n B\n C");
+    }
+
+    #[test]
+    fn test_draw_text_arrows() {
+        let mut canvas = Canvas::new(2, 2);
+        let t = Text::new(0, 0, "a --> b <-- c <--> d -- e ->\n--->");
+        canvas.edit(t.arrow_edits(&Theme::UNICODE).into_iter());
+        assert_eq!(
+            canvas.to_string().trim(),
+            "\
+a ──▶ b ◀── c ◀──▶ d -- e ->
+───▶"
+        )
+    }
+
+    #[test]
+    fn test_draw_text_wrap() {
+        let mut canvas = Canvas::new(0, 0);
+        let t = Text {
+            wrap_width: Some(10),
+            ..Text::new(1, 1, "the quick brown fox jumps over the lazy dog")
+        };
+        canvas.edit(t.edits().into_iter());
+        assert_snapshot!(canvas.to_string())
+    }
+
+    #[test]
+    fn test_wrap_keeps_spacing() {
+        let t = Text {
+            wrap_width: Some(8),
+            ..Text::new(0, 0, "  a  b\nfoo  bar baz\n  indented words")
+        };
+        assert_eq!(
+            t.lines(),
+            ["  a  b", "foo  bar", "baz", "  indented", "words"]
+        );
+        assert_eq!(t.end(), UVec { x: 5, y: 4 });
+    }
+
     #[test]
     fn test_draw_text_multiline() {
         let mut canvas = Canvas::new(2, 2);
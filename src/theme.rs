@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{line::LineGlyphs, rect::RectGlyphs};
+
+// The characters used to draw arrowheads, by the direction they point.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ArrowGlyphs {
+    pub up: char,
+    pub down: char,
+    pub left: char,
+    pub right: char,
+}
+
+// The characters used to draw each kind of shape.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Theme {
+    pub rect: RectGlyphs,
+    pub line: LineGlyphs,
+    pub arrow: ArrowGlyphs,
+}
+
+impl Theme {
+    pub const ASCII: Theme = Theme {
+        rect: RectGlyphs::ASCII,
+        line: LineGlyphs::SINGLE,
+        arrow: ArrowGlyphs {
+            up: '^',
+            down: 'v',
+            left: '<',
+            right: '>',
+        },
+    };
+
+    pub const UNICODE: Theme = Theme {
+        rect: RectGlyphs::SQUARE,
+        line: LineGlyphs {
+            horizontal: '─',
+            vertical: '│',
+            corner: '┼',
+        },
+        arrow: ArrowGlyphs {
+            up: '▲',
+            down: '▼',
+            left: '◀',
+            right: '▶',
+        },
+    };
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinTheme {
+    Ascii,
+    Unicode,
+}
+
+// A theme is configured either by the name of a builtin, or as a table of
+// glyphs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ThemeConfig {
+    Builtin(BuiltinTheme),
+    Custom(Theme),
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig::Builtin(BuiltinTheme::Ascii)
+    }
+}
+
+impl ThemeConfig {
+    pub fn theme(&self) -> Theme {
+        match self {
+            ThemeConfig::Builtin(BuiltinTheme::Ascii) => Theme::ASCII,
+            ThemeConfig::Builtin(BuiltinTheme::Unicode) => Theme::UNICODE,
+            ThemeConfig::Custom(t) => *t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{canvas::Canvas, config::Config, rect::Rect};
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_theme_builtin() {
+        let c = Config::read("theme = \"unicode\"").unwrap();
+        assert_eq!(c.theme.theme(), Theme::UNICODE);
+    }
+
+    #[test]
+    fn test_theme_custom() {
+        let s = toml::toml! {
+            [theme.rect]
+            top_left = "*"
+            top_right = "*"
+            bottom_left = "*"
+            bottom_right = "*"
+            horizontal = "="
+            vertical = "!"
+
+            [theme.line]
+            horizontal = "~"
+            vertical = ":"
+            corner = "o"
+
+            [theme.arrow]
+            up = "A"
+            down = "V"
+            left = "<"
+            right = ">"
+        }
+        .to_string();
+
+        let theme = Config::read(&s).unwrap().theme.theme();
+        let mut canvas = Canvas::new(4, 3);
+        let rect = Rect {
+            glyphs: theme.rect,
+            ..Rect::new(0, 0, 3, 2)
+        };
+        canvas.edit(rect.edits().into_iter());
+        assert_eq!(canvas.to_string(), "*==*\n!  !\n*==*");
+    }
+}
@@ -4,19 +4,82 @@ use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 
 use ratatui::{
     prelude::*,
-    widgets::{block::Title, Block, Paragraph},
+    widgets::{block::Title, Block},
 };
 
 use crate::{
+    ansi,
     binds::Binds,
     canvas::Canvas,
-    config::{Action, Config},
-    line::Line,
-    rect::Rect,
+    config::{
+        Action, Config, CursorConfinement, LineOrder, MarkerStyle, ModeSwitch, NormalEnter,
+        SymlinkSave,
+    },
+    edit::Edit,
+    line::{Line, LineGlyphs, Weight},
+    paint::Paint,
+    rect::{Rect, RectGlyphs, RectStyle},
+    session::{self, Session},
+    table::Table,
     text::Text,
+    theme::{BuiltinTheme, Theme, ThemeConfig},
     vec::{IVec, UVec},
 };
 
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Rect(_) => "rect",
+            Mode::Line(_) => "line",
+            Mode::Text(_) => "text",
+            Mode::Paint(_) => "paint",
+            Mode::SelectRect { .. } => "select_rect",
+            Mode::SelectLine { .. } => "select_line",
+            Mode::Select { .. } => "select",
+            Mode::Erase { .. } => "erase",
+            Mode::Prompt(_) => "prompt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PromptKind {
+    // Resize the canvas to WxH.
+    Resize,
+    // Name a scratch canvas and save it.
+    SaveAs,
+    // Discard unsaved changes for a new canvas.
+    ConfirmNew,
+    // Discard unsaved changes and quit.
+    ConfirmQuit,
+    // Draw a table of RxC cells, each WxH, at the cursor.
+    Table,
+    // Move the cursor to x,y.
+    Goto,
+    // Draw the rect as a window with the given title.
+    Window(Rect),
+}
+
+impl PromptKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PromptKind::Resize => "size (WxH)",
+            PromptKind::SaveAs => "save as",
+            PromptKind::ConfirmNew | PromptKind::ConfirmQuit => "discard changes? (y/n)",
+            PromptKind::Table => "table (RxC WxH)",
+            PromptKind::Goto => "go to (x,y)",
+            PromptKind::Window(_) => "title",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Prompt {
+    kind: PromptKind,
+    input: String,
+}
+
 #[derive(Default, Debug)]
 enum Mode {
     #[default]
@@ -25,41 +88,156 @@ enum Mode {
     Rect(Rect),
     Line(Line),
     Text(Text),
+    Paint(Paint),
 
     SelectRect {
         cursor_start: UVec,
         original: Rect,
         current: Rect,
     },
+    // Moving the end of an existing line.
+    SelectLine {
+        original: Line,
+        current: Line,
+    },
+    // A free region spanning from anchor to the cursor.
+    Select {
+        anchor: UVec,
+    },
+    // A region spanning from anchor to the cursor, cleared on confirm.
+    Erase {
+        anchor: UVec,
+    },
+    // Reading a line of input to complete a command.
+    Prompt(Prompt),
+}
+
+// Moves in the same direction within this long of each other accelerate.
+const ACCEL_WINDOW: std::time::Duration = std::time::Duration::from_millis(150);
+// The number of moves at each speed before speeding up.
+const ACCEL_THRESHOLD: u32 = 4;
+// The most cells to move at once.
+const ACCEL_MAX: i16 = 4;
+
+// A second cancel within this long of the first quits, if double_esc_quit is set.
+const DOUBLE_ESC_WINDOW: std::time::Duration = std::time::Duration::from_millis(500);
+
+// Tracks consecutive moves in one direction to speed up the cursor.
+#[derive(Debug, Default)]
+struct Acceleration {
+    last: Option<((i16, i16), std::time::Instant)>,
+    streak: u32,
+}
+
+impl Acceleration {
+    // The number of cells to move in dir at time now.
+    fn step(&mut self, dir: (i16, i16), now: std::time::Instant) -> i16 {
+        self.streak = match self.last {
+            Some((d, t)) if d == dir && now.duration_since(t) < ACCEL_WINDOW => self.streak + 1,
+            _ => 0,
+        };
+        self.last = Some((dir, now));
+        std::cmp::min(1 + (self.streak / ACCEL_THRESHOLD) as i16, ACCEL_MAX)
+    }
 }
 
 #[derive(Default)]
 struct App {
+    config: Config,
     binds: Binds,
     cursor: UVec,
     canvas: Canvas,
+    // The canvas cell drawn at the top left of the screen.
+    viewport: UVec,
+    // How many cells of the canvas fit on screen, as of the last draw.
+    view_size: UVec,
     exit: bool,
     mode: Mode,
-    path: std::path::PathBuf,
+    mark: Option<UVec>,
+    weight: Weight,
+    // None for a scratch canvas that has not been saved.
+    path: Option<std::path::PathBuf>,
     undo_cursor_pos: Vec<UVec>,
     redo_cursor_pos: Vec<UVec>,
     last_edit_cursor_pos: UVec,
+    // The segments added to the line being drawn, and whether each pushed an
+    // undo frame.
+    line_segments: Vec<(Line, bool)>,
+    // The rows of the last copied region.
+    clipboard: Vec<Vec<char>>,
+    accel: Acceleration,
+    // When the last action was a cancel in normal mode.
+    last_esc: Option<std::time::Instant>,
+    // Whether the last action was a smart home.
+    last_home: bool,
+    // The action RepeatLast applies again.
+    last_action: Option<Action>,
+    // A failure to report in the status until the next key.
+    error: Option<String>,
+    // The canvas content when last loaded or saved.
+    saved: String,
 }
 
 impl App {
-    fn new(config: Config, path: std::path::PathBuf) -> Result<Self> {
-        let canvas = if std::fs::exists(&path)? {
+    fn new(config: Config, path: Option<std::path::PathBuf>) -> Result<Self> {
+        // fail, rather than later overwrite a file that could not be checked
+        let existing = match &path {
+            Some(p) if std::fs::exists(p)? => Some(p),
+            _ => None,
+        };
+        let mut canvas = if let Some(path) = existing {
             log::debug!("Loading from {path:?}");
-            let content = std::fs::read_to_string(&path)?;
+            let content = std::fs::read_to_string(path)?;
             log::trace!("Loading content:\n{content:?}");
-            Canvas::from_str(&content)
+            for (line, len) in Canvas::long_lines(&content, config.max_line_length) {
+                log::warn!("Line {line} of {path:?} is {len} characters long");
+            }
+            // colors are kept apart from the grid, so escapes don't fill cells
+            let (content, colors) = ansi::parse(&content);
+            let mut canvas = Canvas::from_str(&content);
+            if config.trim_on_load {
+                canvas.trim();
+            }
+            canvas.set_colors(colors);
+            canvas
         } else {
             log::debug!("Creating new canvas");
-            Canvas::new(32, 32)
+            Canvas::new(config.new_width, config.new_height)
         };
-        let binds = Binds::from_config(config.binds)?;
+        canvas.set_merge_window(std::time::Duration::from_millis(config.undo_merge_ms));
+        if config.persist_undo {
+            if let Some(history) = path.as_deref().and_then(session::load_history) {
+                canvas.restore_history(history);
+            }
+        }
+        let binds = Binds::from_config(config.binds.clone())?;
         log::trace!("Using binds: {binds:#?}");
+        // resume where the last session left off
+        let mut session = match path.as_ref().filter(|p| Session::path(p).exists()) {
+            Some(p) => Session::load(p),
+            None => Session::default(),
+        };
+        // an explicit cursor wins over the one saved with the session
+        if let Some(cursor) = config.cursor {
+            session.cursor = cursor;
+        }
+        let dim = canvas.dimensions();
+        let cursor = UVec {
+            x: session.cursor.x.min(dim.x.saturating_sub(1)),
+            y: session.cursor.y.min(dim.y.saturating_sub(1)),
+        };
+        // never scrolled past the cursor
+        let viewport = UVec {
+            x: session.viewport.x.min(cursor.x),
+            y: session.viewport.y.min(cursor.y),
+        };
+        log::debug!("Starting cursor at {cursor:?}, viewport at {viewport:?}");
         Ok(Self {
+            cursor,
+            viewport,
+            weight: config.weight,
+            saved: canvas.to_string(),
+            config,
             path,
             binds,
             canvas,
@@ -75,10 +253,102 @@ impl App {
         Ok(())
     }
 
-    fn draw(&self, frame: &mut Frame) {
-        frame.render_widget(self, frame.area());
-        // +1 to accomodate border size
-        frame.set_cursor_position((self.cursor.x + 1, self.cursor.y + 1));
+    fn draw(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        let view = self.canvas_area(area);
+        self.view_size = UVec {
+            x: view.width,
+            y: view.height,
+        };
+        self.scroll_to_cursor();
+        frame.render_widget(&*self, area);
+        frame.set_cursor_position((
+            self.cursor.x - self.viewport.x + view.x,
+            self.cursor.y - self.viewport.y + view.y,
+        ));
+    }
+
+    // Where the canvas is drawn within area, inside the border and gutter.
+    // Without a border, the bottom row is kept for the status.
+    fn canvas_area(&self, area: ratatui::prelude::Rect) -> ratatui::prelude::Rect {
+        let inner = if self.config.border {
+            ratatui::prelude::Rect::new(
+                area.x + 1,
+                area.y + 1,
+                area.width.saturating_sub(2),
+                area.height.saturating_sub(2),
+            )
+        } else {
+            ratatui::prelude::Rect::new(area.x, area.y, area.width, area.height.saturating_sub(1))
+        };
+        let gutter = self.gutter_width(inner.height).min(inner.width);
+        ratatui::prelude::Rect::new(
+            inner.x + gutter,
+            inner.y,
+            inner.width - gutter,
+            inner.height,
+        )
+    }
+
+    // Room for relative line numbers on a screen of height rows, and a space.
+    fn gutter_width(&self, height: u16) -> u16 {
+        if self.config.relative_numbers {
+            height.max(1).ilog10() as u16 + 2
+        } else {
+            0
+        }
+    }
+
+    // Scroll just far enough to show the cursor.
+    fn scroll_to_cursor(&mut self) {
+        let scroll = |c: u16, v: u16, size: u16| {
+            if c < v {
+                c
+            } else if size > 0 && c >= v + size {
+                c + 1 - size
+            } else {
+                v
+            }
+        };
+        self.viewport = UVec {
+            x: scroll(self.cursor.x, self.viewport.x, self.view_size.x),
+            y: scroll(self.cursor.y, self.viewport.y, self.view_size.y),
+        };
+    }
+
+    // Scroll so the cursor is in the middle of the screen, without showing
+    // more than needed past the edge of the canvas.
+    fn center_cursor(&mut self) {
+        let dim = self.canvas.dimensions();
+        let center =
+            |c: u16, size: u16, max: u16| c.saturating_sub(size / 2).min(max.saturating_sub(size));
+        self.viewport = UVec {
+            x: center(self.cursor.x, self.view_size.x, dim.x),
+            y: center(self.cursor.y, self.view_size.y, dim.y),
+        };
+        log::debug!("Centered viewport at {:?}", self.viewport);
+    }
+
+    // Translate a region of the canvas to where it is drawn within inner,
+    // cropped to what is visible.
+    fn to_screen(
+        &self,
+        region: ratatui::prelude::Rect,
+        inner: ratatui::prelude::Rect,
+    ) -> ratatui::prelude::Rect {
+        let visible = ratatui::prelude::Rect::new(
+            self.viewport.x,
+            self.viewport.y,
+            inner.width,
+            inner.height,
+        );
+        let r = region.intersection(visible);
+        ratatui::prelude::Rect::new(
+            inner.x + r.x - self.viewport.x,
+            inner.y + r.y - self.viewport.y,
+            r.width,
+            r.height,
+        )
     }
 
     fn handle_events(&mut self) -> Result<()> {
@@ -93,10 +363,100 @@ impl App {
         Ok(())
     }
 
+    fn rect_glyphs(&self) -> RectGlyphs {
+        match (self.config.rect_style, self.weight) {
+            (RectStyle::Square, Weight::Single) => self.config.theme.theme().rect,
+            (style, weight) => style.glyphs(weight),
+        }
+    }
+
+    fn line_glyphs(&self) -> LineGlyphs {
+        match self.weight {
+            Weight::Single => self.config.theme.theme().line,
+            Weight::Double => LineGlyphs::DOUBLE,
+        }
+    }
+
+    // Text shown in the top right of the border, if any.
+    fn status(&self) -> Option<String> {
+        if let Some(err) = &self.error {
+            return Some(err.clone());
+        }
+        if let Mode::Prompt(p) = &self.mode {
+            return Some(format!("{}: {}", p.kind.label(), p.input));
+        }
+        if let Mode::SelectRect {
+            cursor_start,
+            current,
+            ..
+        } = &self.mode
+        {
+            let offset = self.cursor - *cursor_start;
+            return Some(format!(
+                "{}x{} offset {},{}",
+                current.top_left.x.abs_diff(current.bottom_right.x) + 1,
+                current.top_left.y.abs_diff(current.bottom_right.y) + 1,
+                offset.x,
+                offset.y,
+            ));
+        }
+        let mark = self.mark?;
+        Some(format!(
+            "{}x{} manhattan:{} chebyshev:{}",
+            mark.x.abs_diff(self.cursor.x),
+            mark.y.abs_diff(self.cursor.y),
+            mark.manhattan(self.cursor),
+            mark.chebyshev(self.cursor),
+        ))
+    }
+
+    // The rect styles that may be selected, preferring the active style.
+    fn rect_styles(&self) -> Vec<RectGlyphs> {
+        std::iter::once(self.rect_glyphs())
+            .chain(RectStyle::ALL.map(|s| s.glyphs(Weight::Single)))
+            .chain([RectGlyphs::DOUBLE])
+            .collect()
+    }
+
+    // The line styles that may be selected, preferring the active style.
+    fn line_styles(&self) -> Vec<LineGlyphs> {
+        vec![self.line_glyphs(), LineGlyphs::SINGLE, LineGlyphs::DOUBLE]
+    }
+
     fn move_cursor(&mut self, x: i16, y: i16) {
-        self.cursor.x = self.cursor.x.saturating_add_signed(x);
-        self.cursor.y = self.cursor.y.saturating_add_signed(y);
+        // typed text may run past the edge, and the cursor must follow it
+        let wrap = self.config.wrap_cursor && !matches!(self.mode, Mode::Text(_));
+        if self.config.expand_up_left && !wrap {
+            let past = |c: u16, d: i16| (-(c as i32 + d as i32)).max(0) as u16;
+            let grow = UVec {
+                x: past(self.cursor.x, x),
+                y: past(self.cursor.y, y),
+            };
+            if grow != UVec::default() {
+                self.shift(grow);
+            }
+        }
+        let before = self.cursor;
+        self.cursor = if wrap {
+            self.cursor.wrapped(IVec { x, y }, self.canvas.dimensions())
+        } else {
+            self.cursor + IVec { x, y }
+        };
+        // shapes may still be drawn out into empty space
+        if self.config.cursor_confinement == CursorConfinement::Content
+            && matches!(self.mode, Mode::Normal)
+        {
+            let end = self
+                .canvas
+                .rows()
+                .get(self.cursor.y as usize)
+                .and_then(|row| row.iter().rposition(|c| *c != ' '))
+                .map_or(0, |i| i as u16 + 1);
+            self.cursor.x = self.cursor.x.min(end);
+        }
         log::debug!("Moved cursor to ({:?})", self.cursor);
+        // shapes follow the cursor, even if it wrapped
+        let d = self.cursor - before;
         match &mut self.mode {
             Mode::Normal => {}
             Mode::Rect(r) => {
@@ -104,62 +464,405 @@ impl App {
                 log::debug!("Updated rect to {r:?}");
             }
             Mode::Line(l) => {
-                l.end = self.cursor;
+                l.end = if self.config.snap_lines {
+                    Line::snapped(l.start, self.cursor)
+                } else {
+                    self.cursor
+                };
                 log::debug!("Updated line to {l:?}");
             }
             Mode::Text(_) => {}
+            Mode::Paint(p) => {
+                if p.char.is_some() {
+                    p.toggle(self.cursor);
+                    log::debug!("Updated paint to {p:?}");
+                }
+            }
             Mode::SelectRect { current, .. } => {
-                *current = current.translated(IVec { x, y });
+                *current = current.translated(d);
                 log::debug!("Translated rect to {current:?}");
             }
+            Mode::SelectLine { current, .. } => {
+                current.end = self.cursor;
+                log::debug!("Updated line to {current:?}");
+            }
+            Mode::Select { anchor } => {
+                log::debug!("Updated selection to {anchor:?}-{:?}", self.cursor);
+            }
+            Mode::Erase { anchor } => {
+                log::debug!("Updated eraser to {anchor:?}-{:?}", self.cursor);
+            }
+            Mode::Prompt(_) => {}
+        }
+    }
+
+    // Grow the canvas up and left by d, moving the cursor and everything
+    // placed on the canvas along with its content.
+    fn shift(&mut self, d: UVec) {
+        self.canvas.shift(d);
+        let d = IVec {
+            x: d.x as i16,
+            y: d.y as i16,
+        };
+        let shift = |p: &mut UVec| *p = *p + d;
+        let shift_line = |l: &mut Line| {
+            shift(&mut l.start);
+            shift(&mut l.end);
+        };
+
+        shift(&mut self.cursor);
+        shift(&mut self.last_edit_cursor_pos);
+        self.mark.iter_mut().for_each(shift);
+        self.undo_cursor_pos.iter_mut().for_each(shift);
+        self.redo_cursor_pos.iter_mut().for_each(shift);
+        self.line_segments
+            .iter_mut()
+            .for_each(|(l, _)| shift_line(l));
+        match &mut self.mode {
+            Mode::Normal | Mode::Prompt(_) => {}
+            Mode::Rect(r) => *r = r.translated(d),
+            Mode::Line(l) => shift_line(l),
+            Mode::Text(t) => shift(&mut t.start),
+            Mode::Paint(p) => p.cells.iter_mut().for_each(shift),
+            Mode::SelectRect {
+                cursor_start,
+                original,
+                current,
+            } => {
+                shift(cursor_start);
+                *original = original.translated(d);
+                *current = current.translated(d);
+            }
+            Mode::SelectLine { original, current } => {
+                shift_line(original);
+                shift_line(current);
+            }
+            Mode::Select { anchor } | Mode::Erase { anchor } => shift(anchor),
+        }
+        log::debug!("Shifted by {d:?}, cursor now at {:?}", self.cursor);
+    }
+
+    // Move the content by d, with the cursor following it.
+    fn shift_canvas(&mut self, d: IVec) {
+        if self.canvas.translate(d) {
+            self.undo_cursor_pos.push(self.cursor);
+            self.redo_cursor_pos.clear();
+            self.cursor = self.cursor + d;
+            self.last_edit_cursor_pos = self.cursor;
+        }
+    }
+
+    fn new_document(&mut self) {
+        log::info!("Starting a new document");
+        self.canvas.reset(UVec {
+            x: self.config.new_width,
+            y: self.config.new_height,
+        });
+        self.undo_cursor_pos.push(self.cursor);
+        self.redo_cursor_pos.clear();
+        self.cursor = UVec::default();
+        self.mode = Mode::Normal;
+        // a save must not overwrite the old document
+        self.path = None;
+        self.saved = self.canvas.to_string();
+    }
+
+    // Move the cursor one cell, or more if accelerating.
+    fn step_cursor(&mut self, x: i16, y: i16) {
+        let n = if self.config.accelerate {
+            self.accel.step((x, y), std::time::Instant::now())
+        } else {
+            1
+        };
+        self.move_cursor(x * n, y * n);
+    }
+
+    // Move the cursor to the next edge of content in direction d.
+    fn jump(&mut self, d: IVec) {
+        let d = self.canvas.jump(self.cursor, d) - self.cursor;
+        self.move_cursor(d.x, d.y);
+    }
+
+    // Move to the first non-blank cell of the row, then to column 0 if pressed
+    // again from there.
+    fn smart_home(&mut self, again: bool) {
+        if let Mode::Text(_) = self.mode {
+            log::debug!("Ignoring smart home while typing");
+            return;
+        }
+        let indent = self
+            .canvas
+            .rows()
+            .get(self.cursor.y as usize)
+            .and_then(|row| row.iter().position(|c| *c != ' '))
+            .unwrap_or(0) as u16;
+        let x = if again && self.cursor.x == indent {
+            0
+        } else {
+            indent
+        };
+        log::debug!("Moving home from {:?} to column {x}", self.cursor);
+        self.move_cursor(x as i16 - self.cursor.x as i16, 0);
+        self.last_home = true;
+    }
+
+    // Move to the start of the next label after the cursor, or the previous
+    // one before it, wrapping around the canvas.
+    fn cycle_label(&mut self, forward: bool) {
+        if !matches!(self.mode, Mode::Normal) {
+            log::debug!("Ignoring label navigation in mode: {:?}", self.mode);
+            return;
+        }
+        let labels = self.canvas.labels();
+        let key = |p: &UVec| (p.y, p.x);
+        let cursor = key(&self.cursor);
+        let target = if forward {
+            labels.iter().find(|p| key(p) > cursor).or(labels.first())
+        } else {
+            labels
+                .iter()
+                .rev()
+                .find(|p| key(p) < cursor)
+                .or(labels.last())
+        };
+        let Some(target) = target.copied() else {
+            log::debug!("No labels to move to");
+            return;
+        };
+        log::debug!("Moving to label at {target:?}");
+        let d = target - self.cursor;
+        self.move_cursor(d.x, d.y);
+    }
+
+    // Begin a selection at the cursor if there is none, then grow it.
+    fn extend_selection(&mut self, x: i16, y: i16) {
+        if let Mode::Normal = self.mode {
+            self.mode = Mode::Select {
+                anchor: self.cursor,
+            };
+            log::debug!("Set mode: {:?}", self.mode);
+        } else if !matches!(self.mode, Mode::Select { .. }) {
+            log::debug!("Ignoring selection in mode: {:?}", self.mode);
+            return;
+        }
+        self.move_cursor(x, y);
+    }
+
+    // Take anything already drawn at the cursor into the text being typed, so
+    // the text flows past it rather than overwriting it.
+    fn skip_occupied(&mut self, dx: i16, dy: i16) {
+        loop {
+            let (x, y) = (self.cursor.x as usize, self.cursor.y as usize);
+            let c = match self.canvas.rows().get(y).and_then(|row| row.get(x)) {
+                Some(c) if *c != ' ' => *c,
+                _ => return,
+            };
+            let Mode::Text(t) = &mut self.mode else {
+                return;
+            };
+            log::debug!("Skipping {c} at {:?}", self.cursor);
+            t.text.push(c);
+            self.move_cursor(dx, dy);
         }
     }
 
+    // Apply an action given by a key or caller, remembering it to repeat.
+    fn dispatch(&mut self, action: Action) -> Result<()> {
+        if action.repeatable() {
+            self.last_action = Some(action.clone());
+        }
+        self.apply_action(action)
+    }
+
     fn apply_action(&mut self, action: Action) -> Result<()> {
+        // only a cancel straight after another counts towards quitting
+        let last_esc = self.last_esc.take();
+        let last_home = std::mem::take(&mut self.last_home);
         match action {
             Action::Quit => {
                 log::info!("Exit requested");
                 self.exit = true;
             }
 
-            Action::Save => {
-                log::info!("Saving to {:?}", self.path);
-                std::fs::write(&self.path, self.canvas.to_string())?;
-            }
-
-            Action::MoveCursorUp => self.move_cursor(0, -1),
-            Action::MoveCursorDown => self.move_cursor(0, 1),
-            Action::MoveCursorLeft => self.move_cursor(-1, 0),
-            Action::MoveCursorRight => self.move_cursor(1, 0),
+            Action::Save => match &self.path {
+                Some(path) => {
+                    if self.config.symlink_save == SymlinkSave::Replace && path.is_symlink() {
+                        log::info!("Replacing symlink {path:?}");
+                        std::fs::remove_file(path)?;
+                    }
+                    let missing = path
+                        .parent()
+                        .filter(|d| !d.as_os_str().is_empty() && !d.exists());
+                    if let Some(dir) = missing {
+                        if !self.config.create_dirs {
+                            let msg = format!("no dir {}, see create_dirs", dir.display());
+                            log::warn!("Not saving to {path:?}: {msg}");
+                            self.error = Some(msg);
+                            return Ok(());
+                        }
+                        log::info!("Creating directory {dir:?}");
+                        std::fs::create_dir_all(dir)?;
+                    }
+                    log::info!("Saving to {path:?}");
+                    std::fs::write(path, self.canvas.to_ansi_string())?;
+                    self.saved = self.canvas.to_string();
+                    let session = Session {
+                        cursor: self.cursor,
+                        viewport: self.viewport,
+                    };
+                    if let Err(err) = session.save(path) {
+                        log::warn!("Failed to save session: {err:?}");
+                    }
+                    if self.config.persist_undo {
+                        if let Err(err) = session::save_history(&self.canvas.history(), path) {
+                            log::warn!("Failed to save undo history: {err:?}");
+                        }
+                    }
+                }
+                None => {
+                    log::debug!("No path to save to, prompting for one");
+                    self.mode = Mode::Prompt(Prompt {
+                        kind: PromptKind::SaveAs,
+                        input: String::new(),
+                    });
+                }
+            },
 
+            Action::MoveCursorUp => self.step_cursor(0, -1),
+            Action::MoveCursorDown => self.step_cursor(0, 1),
+            Action::MoveCursorLeft => self.step_cursor(-1, 0),
+            Action::MoveCursorRight => self.step_cursor(1, 0),
+
+            Action::JumpUp => self.jump(IVec::UP),
+            Action::JumpDown => self.jump(IVec::DOWN),
+            Action::JumpLeft => self.jump(IVec::LEFT),
+            Action::JumpRight => self.jump(IVec::RIGHT),
+            Action::SmartHome => self.smart_home(last_home),
+            Action::NextLabel => self.cycle_label(true),
+            Action::PrevLabel => self.cycle_label(false),
+
+            Action::ExtendSelectionUp => self.extend_selection(0, -1),
+            Action::ExtendSelectionDown => self.extend_selection(0, 1),
+            Action::ExtendSelectionLeft => self.extend_selection(-1, 0),
+            Action::ExtendSelectionRight => self.extend_selection(1, 0),
+
+            // finish the shape in progress before starting another
+            Action::DrawRect
+            | Action::DrawLine
+            | Action::DrawDimension
+            | Action::DrawText
+            | Action::DrawTextVertical
+            | Action::Paint
+                if matches!(
+                    self.mode,
+                    Mode::Rect(_) | Mode::Line(_) | Mode::Text(_) | Mode::Paint(_)
+                ) =>
+            {
+                match self.config.mode_switch {
+                    ModeSwitch::Discard => {
+                        log::warn!("Discarding unfinished {:?}", self.mode);
+                    }
+                    ModeSwitch::Confirm => {
+                        log::debug!("Confirming {:?} before {action:?}", self.mode);
+                        self.apply_action(Action::ConfirmShape)?;
+                    }
+                }
+                // confirming may start another shape with sticky_tools
+                self.mode = Mode::Normal;
+                self.apply_action(action)?;
+            }
             Action::DrawRect => {
+                let corners: Vec<_> = self
+                    .rect_styles()
+                    .iter()
+                    .flat_map(|g| g.corners())
+                    .collect();
+                if let Some(corner) =
+                    self.canvas
+                        .nearest(self.cursor, self.config.corner_snap, &corners)
+                {
+                    log::debug!("Snapping rect to corner at {corner:?}");
+                    self.cursor = corner;
+                }
                 self.mode = Mode::Rect(Rect {
                     top_left: self.cursor,
                     bottom_right: self.cursor,
+                    glyphs: self.rect_glyphs(),
+                    shadow: self.config.shadow,
                 });
                 self.move_cursor(1, 1);
                 log::debug!("Set mode: {:?}", self.mode);
             }
-            Action::DrawLine => {
-                self.mode = Mode::Line(Line::new(self.cursor, self.cursor));
+            Action::DrawLine | Action::DrawDimension => {
+                self.line_segments.clear();
+                self.mode = Mode::Line(Line {
+                    glyphs: self.line_glyphs(),
+                    mirror: self.config.line_order == LineOrder::HorizontalFirst,
+                    dimension: matches!(action, Action::DrawDimension),
+                    ..Line::new(self.cursor, self.cursor)
+                });
                 log::debug!("Set mode: {:?}", self.mode);
             }
-            Action::DrawText => {
+            Action::DrawText | Action::DrawTextVertical => {
                 self.mode = Mode::Text(Text {
                     start: self.cursor,
                     text: "".into(),
+                    vertical: matches!(action, Action::DrawTextVertical),
+                    wrap_width: self.config.text_wrap_width,
                 });
                 log::debug!("Set mode: {:?}", self.mode);
             }
+            Action::Paint => {
+                self.mode = Mode::Paint(Paint::default());
+                log::debug!("Set mode: {:?}", self.mode);
+            }
 
             Action::LineAddPoint => match &mut self.mode {
                 Mode::Line(l) => {
                     log::debug!("Adding point to line: {l:?}");
-                    self.canvas.edit(l.edits().into_iter());
-                    self.undo_cursor_pos.push(l.start);
+                    let pushed = self.canvas.draw(l.edits().into_iter());
+                    if pushed {
+                        self.undo_cursor_pos.push(l.start);
+                    }
+                    self.line_segments.push((*l, pushed));
                     self.redo_cursor_pos.clear();
                     self.last_edit_cursor_pos = self.cursor;
-                    self.mode = Mode::Line(Line::new(l.end, l.end));
+                    self.mode = Mode::Line(Line {
+                        glyphs: l.glyphs,
+                        mirror: self.config.line_order == LineOrder::HorizontalFirst,
+                        dimension: l.dimension,
+                        ..Line::new(l.end, l.end)
+                    });
+                }
+                _ => {}
+            },
+
+            Action::LinePopPoint => match (&self.mode, self.line_segments.pop()) {
+                (Mode::Line(_), Some((segment, pushed))) => {
+                    log::debug!("Popping line segment: {segment:?}");
+                    // a segment merged into an earlier frame can't be undone alone
+                    if pushed {
+                        self.canvas.undo();
+                        if let Some(pos) = self.undo_cursor_pos.pop() {
+                            self.redo_cursor_pos.push(pos);
+                        }
+                    }
+                    self.cursor = segment.end;
+                    self.mode = Mode::Line(segment);
+                }
+                (_, popped) => {
+                    log::debug!("No line segment to pop");
+                    self.line_segments.extend(popped);
+                }
+            },
+
+            Action::TitleRect => match self.mode {
+                Mode::Rect(r) => {
+                    self.mode = Mode::Prompt(Prompt {
+                        kind: PromptKind::Window(r),
+                        input: String::new(),
+                    });
+                    log::debug!("Set mode: {:?}", self.mode);
                 }
                 _ => {}
             },
@@ -172,57 +875,243 @@ impl App {
                 _ => {}
             },
 
-            Action::ExitMode => match &self.mode {
-                Mode::Normal => {}
+            Action::SwapAnchor => match &mut self.mode {
+                Mode::Rect(r) => {
+                    std::mem::swap(&mut r.top_left, &mut r.bottom_right);
+                    self.cursor = r.bottom_right;
+                    log::debug!("Swapped rect anchor: {r:?}");
+                }
+                Mode::Line(l) => {
+                    std::mem::swap(&mut l.start, &mut l.end);
+                    self.cursor = l.end;
+                    log::debug!("Swapped line anchor: {l:?}");
+                }
+                mode => {
+                    log::debug!("Ignoring swap anchor in mode: {mode:?}");
+                }
+            },
+
+            Action::ToggleWeight => {
+                self.weight = self.weight.toggled();
+                log::debug!("Set weight: {:?}", self.weight);
+                let (rect, line) = (self.rect_glyphs(), self.line_glyphs());
+                match &mut self.mode {
+                    Mode::Rect(r) => r.glyphs = rect,
+                    Mode::Line(l) => l.glyphs = line,
+                    _ => {}
+                }
+            }
+
+            Action::ToggleBorder => {
+                self.config.border = !self.config.border;
+                log::debug!("Set border: {}", self.config.border);
+            }
+            Action::ToggleMinimap => {
+                self.config.minimap = !self.config.minimap;
+                log::debug!("Set minimap: {}", self.config.minimap);
+            }
+            Action::ToggleTheme => {
+                let from = self.config.theme.theme();
+                let (to, builtin) = if from == Theme::UNICODE {
+                    (Theme::ASCII, BuiltinTheme::Ascii)
+                } else {
+                    (Theme::UNICODE, BuiltinTheme::Unicode)
+                };
+                log::info!("Switching theme to {builtin:?}");
+                if self.canvas.restyle(&from, &to) {
+                    self.undo_cursor_pos.push(self.cursor);
+                }
+                self.redo_cursor_pos.clear();
+                self.config.theme = ThemeConfig::Builtin(builtin);
+            }
+            Action::ToggleWhitespace => {
+                self.config.show_whitespace = !self.config.show_whitespace;
+                log::debug!("Set show whitespace: {}", self.config.show_whitespace);
+            }
+
+            Action::ExitMode | Action::ConfirmShape => match &self.mode {
+                Mode::Normal => match self.config.normal_enter {
+                    NormalEnter::Nothing => {}
+                    NormalEnter::Newline => {
+                        log::debug!("Moving cursor to the next line");
+                        self.move_cursor(-(self.cursor.x as i16), 1);
+                    }
+                },
+                Mode::Rect(r) if self.config.sticky_tools && r.top_left == r.bottom_right => {
+                    log::debug!("Nothing drawn since rect restarted, exiting");
+                    self.mode = Mode::Normal;
+                }
                 Mode::Rect(r) => {
                     log::debug!("Confirming rect {r:?}");
-                    self.canvas.edit(r.edits().into_iter());
-                    self.undo_cursor_pos.push(r.top_left);
+                    let shadow = r.shadow_edits(&self.canvas);
+                    if self.canvas.draw(r.edits().into_iter().chain(shadow)) {
+                        self.undo_cursor_pos.push(r.top_left);
+                    }
                     self.redo_cursor_pos.clear();
                     self.last_edit_cursor_pos = self.cursor;
+                    self.mode = if self.config.sticky_tools {
+                        Mode::Rect(Rect {
+                            top_left: self.cursor,
+                            bottom_right: self.cursor,
+                            ..*r
+                        })
+                    } else {
+                        Mode::Normal
+                    };
+                }
+                Mode::Line(l) if self.config.sticky_tools && l.start == l.end => {
+                    log::debug!("Nothing drawn since line restarted, exiting");
+                    self.mode = Mode::Normal;
+                }
+                Mode::Line(l) if self.config.skip_empty_lines && l.start == l.end => {
+                    log::debug!("Skipping zero-length line at {:?}", l.start);
+                    self.line_segments.clear();
                     self.mode = Mode::Normal;
                 }
                 Mode::Line(l) => {
                     log::debug!("Confirming line {l:?}");
-                    self.canvas.edit(l.edits().into_iter());
-                    self.undo_cursor_pos.push(l.start);
+                    if self.canvas.draw(l.edits().into_iter()) {
+                        self.undo_cursor_pos.push(l.start);
+                    }
+                    self.line_segments.clear();
                     self.redo_cursor_pos.clear();
                     self.last_edit_cursor_pos = self.cursor;
-                    self.mode = Mode::Normal;
+                    self.mode = if self.config.sticky_tools {
+                        Mode::Line(Line {
+                            glyphs: l.glyphs,
+                            mirror: self.config.line_order == LineOrder::HorizontalFirst,
+                            dimension: l.dimension,
+                            ..Line::new(self.cursor, self.cursor)
+                        })
+                    } else {
+                        Mode::Normal
+                    };
                 }
                 Mode::Text(t) => {
                     log::debug!("Confirming text {t:?}");
-                    self.canvas.edit(t.edits().into_iter());
-                    self.undo_cursor_pos.push(t.start);
+                    let edits = if self.config.text_arrows {
+                        t.arrow_edits(&self.config.theme.theme())
+                    } else {
+                        t.edits()
+                    };
+                    if self.canvas.edit(edits.into_iter()) {
+                        self.undo_cursor_pos.push(t.start);
+                    }
                     self.redo_cursor_pos.clear();
                     self.last_edit_cursor_pos = self.cursor;
                     self.mode = Mode::Normal;
                 }
+                Mode::Paint(p) => {
+                    log::debug!("Confirming paint {p:?}");
+                    if let (Some(_), Some(start)) = (p.char, p.cells.first()) {
+                        if self.canvas.edit(p.edits().into_iter()) {
+                            self.undo_cursor_pos.push(*start);
+                        }
+                        self.redo_cursor_pos.clear();
+                        self.last_edit_cursor_pos = self.cursor;
+                    }
+                    self.mode = Mode::Normal;
+                }
                 Mode::SelectRect {
                     cursor_start,
                     original,
                     current,
                 } => {
                     log::debug!("Deselecing rect {current:?}");
-                    self.canvas.edit(
+                    if self.canvas.draw(
                         original
                             .edits()
                             .into_iter()
-                            .map(|e| e.erase())
+                            .map(|e| e.erase(self.config.erase_char))
                             .chain(current.edits().into_iter()),
-                    );
-                    self.undo_cursor_pos.push(*cursor_start);
+                    ) {
+                        self.undo_cursor_pos.push(*cursor_start);
+                    }
+                    self.redo_cursor_pos.clear();
+                    self.last_edit_cursor_pos = self.cursor;
+                    self.mode = Mode::Normal;
+                }
+                Mode::SelectLine { original, current } => {
+                    log::debug!("Deselecing line {current:?}");
+                    if self.canvas.draw(
+                        original
+                            .edits()
+                            .into_iter()
+                            .map(|e| e.erase(self.config.erase_char))
+                            .chain(current.edits()),
+                    ) {
+                        self.undo_cursor_pos.push(original.end);
+                    }
+                    self.redo_cursor_pos.clear();
+                    self.last_edit_cursor_pos = self.cursor;
+                    self.mode = Mode::Normal;
+                }
+                Mode::Select { .. } => {
+                    log::debug!("Clearing selection");
+                    self.mode = Mode::Normal;
+                }
+                Mode::Erase { anchor } => {
+                    let region = Rect::new(anchor.x, anchor.y, self.cursor.x, self.cursor.y);
+                    log::debug!("Erasing region {region:?}");
+                    if self.canvas.erase_region(&region, self.config.erase_char) {
+                        self.undo_cursor_pos.push(*anchor);
+                    }
                     self.redo_cursor_pos.clear();
                     self.last_edit_cursor_pos = self.cursor;
                     self.mode = Mode::Normal;
                 }
+                Mode::Prompt(p) => {
+                    log::debug!("Cancelling prompt {p:?}");
+                    self.mode = Mode::Normal;
+                }
+            },
+
+            Action::CancelShape => match &self.mode {
+                // enter adds a newline to text, so this is the only way to finish it
+                Mode::Text(_) => self.apply_action(Action::ConfirmShape)?,
+                Mode::Normal if self.config.double_esc_quit => {
+                    let now = std::time::Instant::now();
+                    match last_esc {
+                        Some(t) if now.duration_since(t) < DOUBLE_ESC_WINDOW => {
+                            if self.canvas.to_string() == self.saved {
+                                self.apply_action(Action::Quit)?;
+                            } else {
+                                self.mode = Mode::Prompt(Prompt {
+                                    kind: PromptKind::ConfirmQuit,
+                                    input: String::new(),
+                                });
+                                log::debug!("Set mode: {:?}", self.mode);
+                            }
+                        }
+                        _ => self.last_esc = Some(now),
+                    }
+                }
+                mode => {
+                    log::debug!("Cancelling {mode:?}");
+                    self.mode = Mode::Normal;
+                }
             },
 
-            Action::TextAddLine => todo!(),
+            Action::TextAddLine => match &mut self.mode {
+                Mode::Text(s) => {
+                    log::debug!("Appending newline to {s:?}");
+                    let (dx, dy) = if s.vertical { (0, 1) } else { (1, 0) };
+                    let line = s.text.rsplit('\n').next().unwrap_or_default();
+                    let len = line.chars().count() as i16;
+                    s.text.push('\n');
+                    if !self.follow_wrapped_text() {
+                        // back to the start of the line, then on to the next
+                        self.move_cursor(dy - dx * len, dx - dy * len);
+                    }
+                }
+                mode => {
+                    log::debug!("Ignoring add line in mode: {mode:?}");
+                }
+            },
             Action::Delete => match &self.mode {
                 Mode::Normal => {
                     log::debug!("Deleting char at: {:?}", self.cursor);
-                    self.canvas.clear(self.cursor);
+                    self.canvas.clear(self.cursor, self.config.erase_char);
                 }
                 Mode::SelectRect {
                     cursor_start,
@@ -230,9 +1119,24 @@ impl App {
                     ..
                 } => {
                     log::debug!("Deleting rect {original:?}");
-                    self.canvas
-                        .edit(original.edits().into_iter().map(|e| e.erase()));
-                    self.undo_cursor_pos.push(*cursor_start);
+                    if self.canvas.edit(
+                        original
+                            .edits()
+                            .into_iter()
+                            .map(|e| e.erase(self.config.erase_char)),
+                    ) {
+                        self.undo_cursor_pos.push(*cursor_start);
+                    }
+                    self.redo_cursor_pos.clear();
+                    self.last_edit_cursor_pos = self.cursor;
+                    self.mode = Mode::Normal;
+                }
+                Mode::Select { anchor } => {
+                    let region = Rect::new(anchor.x, anchor.y, self.cursor.x, self.cursor.y);
+                    log::debug!("Deleting region {region:?}");
+                    if self.canvas.erase_region(&region, self.config.erase_char) {
+                        self.undo_cursor_pos.push(self.cursor);
+                    }
                     self.redo_cursor_pos.clear();
                     self.last_edit_cursor_pos = self.cursor;
                     self.mode = Mode::Normal;
@@ -242,6 +1146,61 @@ impl App {
                 }
             },
 
+            Action::Copy => match self.mode {
+                Mode::Select { anchor } => {
+                    let region = Rect::new(anchor.x, anchor.y, self.cursor.x, self.cursor.y);
+                    log::debug!("Copying region {region:?}");
+                    self.clipboard = self.canvas.region(&region);
+                    self.mode = Mode::Normal;
+                }
+                ref mode => {
+                    log::debug!("Ignoring copy in mode: {mode:?}");
+                }
+            },
+            Action::Paste => {
+                log::debug!("Pasting {} rows at {:?}", self.clipboard.len(), self.cursor);
+                let edits =
+                    Edit::stamp(self.cursor, &self.clipboard, self.config.transparent_paste);
+                if self.canvas.edit(edits.into_iter()) {
+                    self.undo_cursor_pos.push(self.cursor);
+                }
+                self.redo_cursor_pos.clear();
+                self.last_edit_cursor_pos = self.cursor;
+            }
+
+            Action::EraseLines => match &self.mode {
+                Mode::SelectRect {
+                    cursor_start,
+                    original,
+                    ..
+                } => {
+                    log::debug!("Erasing lines in {original:?}");
+                    if self.canvas.erase_lines(original, self.config.erase_char) {
+                        self.undo_cursor_pos.push(*cursor_start);
+                    }
+                    self.redo_cursor_pos.clear();
+                    self.last_edit_cursor_pos = self.cursor;
+                    self.mode = Mode::Normal;
+                }
+                mode => {
+                    log::debug!("Ignoring erase lines in mode: {mode:?}");
+                }
+            },
+            Action::RepairBoxes => {
+                if self.canvas.repair_boxes(&self.rect_styles()) {
+                    self.undo_cursor_pos.push(self.cursor);
+                }
+                self.redo_cursor_pos.clear();
+            }
+
+            Action::SetMark => {
+                self.mark = match self.mark {
+                    Some(m) if m == self.cursor => None,
+                    _ => Some(self.cursor),
+                };
+                log::debug!("Set mark: {:?}", self.mark);
+            }
+
             Action::Undo => {
                 log::debug!("Undo");
                 self.canvas.undo();
@@ -260,69 +1219,468 @@ impl App {
                     self.cursor = pos;
                 }
             }
-
-            Action::SelectRect => {
-                if let Some(rect) = self.canvas.rect_around(self.cursor) {
+            Action::RepeatLast => match self.last_action.clone() {
+                Some(last) => {
+                    log::debug!("Repeating {last:?}");
+                    self.apply_action(last)?;
+                }
+                None => log::debug!("No action to repeat"),
+            },
+            Action::UndoAll => {
+                self.canvas.undo_all();
+                // as if each undo were done in turn, ending at the earliest
+                let undone = self.undo_cursor_pos.drain(..).rev();
+                self.redo_cursor_pos.extend(undone);
+                if let Some(pos) = self.redo_cursor_pos.last() {
+                    log::debug!("Restoring cursor to {pos:?}");
+                    self.cursor = *pos;
+                }
+            }
+            Action::RedoAll => {
+                self.canvas.redo_all();
+                let redone: Vec<_> = self.redo_cursor_pos.drain(..).collect();
+                if let Some((pos, rest)) = redone.split_first() {
+                    log::debug!("Restoring cursor to {pos:?}");
+                    self.undo_cursor_pos.push(self.cursor);
+                    self.undo_cursor_pos.extend(rest.iter().rev());
+                    self.cursor = *pos;
+                }
+            }
+
+            // selecting again, before the selection is changed, grows it to
+            // the box it is nested in
+            Action::SelectRect
+                if matches!(
+                    &self.mode,
+                    Mode::SelectRect { original, current, .. }
+                        if original.top_left == current.top_left
+                            && original.bottom_right == current.bottom_right
+                ) =>
+            {
+                let Mode::SelectRect {
+                    cursor_start,
+                    original,
+                    ..
+                } = &self.mode
+                else {
+                    unreachable!();
+                };
+                if let Some(rect) = self.canvas.rect_enclosing(
+                    original,
+                    &self.rect_styles(),
+                    self.config.select_radius,
+                ) {
+                    log::info!("Selected enclosing rect {rect:?}");
+                    self.mode = Mode::SelectRect {
+                        cursor_start: *cursor_start,
+                        original: rect,
+                        current: rect,
+                    };
+                } else {
+                    log::info!("No rect encloses {original:?}");
+                }
+            }
+            Action::RotateSelection => match &self.mode {
+                Mode::SelectRect {
+                    original, current, ..
+                } => {
+                    let (a, b) = (original.top_left, original.bottom_right);
+                    let top_left = UVec {
+                        x: a.x.min(b.x),
+                        y: a.y.min(b.y),
+                    };
+                    let region = self.canvas.region(original);
+                    let rows = Canvas::rotate(&region);
+                    let (Some(first), Some(rotated_first)) = (region.first(), rows.first()) else {
+                        log::debug!("Nothing to rotate in {original:?}");
+                        return Ok(());
+                    };
+                    // erase the old content and stamp the rotated content over it
+                    let blank = vec![vec![self.config.erase_char; first.len()]; region.len()];
+                    let edits = Edit::stamp(top_left, &blank, false)
+                        .into_iter()
+                        .chain(Edit::stamp(top_left, &rows, false));
+                    let rotated = Rect {
+                        top_left,
+                        bottom_right: UVec {
+                            x: top_left.x + rotated_first.len() as u16 - 1,
+                            y: top_left.y + rows.len() as u16 - 1,
+                        },
+                        ..*original
+                    };
+                    log::debug!("Rotated {original:?} to {rotated:?}");
+                    // keep any pending move of the selection
+                    let moved = rotated.translated(current.top_left - original.top_left);
+                    if self.canvas.edit(edits) {
+                        self.undo_cursor_pos.push(self.cursor);
+                    }
+                    self.redo_cursor_pos.clear();
+                    self.mode = Mode::SelectRect {
+                        cursor_start: self.cursor,
+                        original: rotated,
+                        current: moved,
+                    };
+                }
+                mode => {
+                    log::debug!("Ignoring rotate in mode: {mode:?}");
+                }
+            },
+            // there is no record of what a crossing shape drew under the rect,
+            // so it can be raised but not lowered
+            Action::RaiseSelection => match &mut self.mode {
+                Mode::SelectRect {
+                    cursor_start,
+                    original,
+                    current,
+                } => {
+                    log::debug!("Raising rect {current:?}");
+                    if self.canvas.edit(
+                        original
+                            .edits()
+                            .into_iter()
+                            .map(|e| e.erase(self.config.erase_char))
+                            .chain(current.edits()),
+                    ) {
+                        self.undo_cursor_pos.push(*cursor_start);
+                    }
+                    self.redo_cursor_pos.clear();
+                    self.last_edit_cursor_pos = self.cursor;
+                    // it stays selected, from where it now is
+                    *original = *current;
+                    *cursor_start = self.cursor;
+                }
+                mode => log::debug!("Ignoring raise in mode: {mode:?}"),
+            },
+            Action::EraseRect => {
+                self.mode = Mode::Erase {
+                    anchor: self.cursor,
+                };
+                log::debug!("Set mode: {:?}", self.mode);
+            }
+            Action::SelectRect => {
+                if let Some(rect) = self.canvas.rect_around(
+                    self.cursor,
+                    &self.rect_styles(),
+                    self.config.select_radius,
+                ) {
                     log::info!("Selected rect {rect:?}");
                     self.mode = Mode::SelectRect {
                         cursor_start: self.cursor,
                         original: rect,
                         current: rect,
                     };
+                } else if self.config.select_fallback {
+                    log::info!("No rect matched, selecting from {:?}", self.cursor);
+                    self.mode = Mode::Select {
+                        anchor: self.cursor,
+                    };
                 } else {
                     log::info!("No rect matched at {:?}", self.cursor);
                 }
             }
+            Action::NewDocument if self.canvas.to_string() != self.saved => {
+                self.mode = Mode::Prompt(Prompt {
+                    kind: PromptKind::ConfirmNew,
+                    input: String::new(),
+                });
+                log::debug!("Set mode: {:?}", self.mode);
+            }
+            Action::NewDocument => self.new_document(),
+            Action::Resize => {
+                self.mode = Mode::Prompt(Prompt {
+                    kind: PromptKind::Resize,
+                    input: String::new(),
+                });
+                log::debug!("Set mode: {:?}", self.mode);
+            }
+            Action::ShiftCanvasUp => self.shift_canvas(IVec::UP),
+            Action::ShiftCanvasDown => self.shift_canvas(IVec::DOWN),
+            Action::ShiftCanvasLeft => self.shift_canvas(IVec::LEFT),
+            Action::ShiftCanvasRight => self.shift_canvas(IVec::RIGHT),
+            Action::DrawTable => {
+                self.mode = Mode::Prompt(Prompt {
+                    kind: PromptKind::Table,
+                    input: String::new(),
+                });
+                log::debug!("Set mode: {:?}", self.mode);
+            }
+            Action::GotoCoordinate => {
+                self.mode = Mode::Prompt(Prompt {
+                    kind: PromptKind::Goto,
+                    input: String::new(),
+                });
+                log::debug!("Set mode: {:?}", self.mode);
+            }
+            Action::SelectLine => {
+                if let Some(line) = self.canvas.line_at(self.cursor, &self.line_styles()) {
+                    log::info!("Selected line {line:?}");
+                    self.mode = Mode::SelectLine {
+                        original: line,
+                        current: line,
+                    };
+                } else {
+                    log::info!("No line end at {:?}", self.cursor);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Run the command for a completed prompt.
+    fn submit(&mut self, kind: PromptKind, input: &str) -> Result<()> {
+        // parse a pair of numbers like 3x4
+        let pair = |s: &str| -> Option<(u16, u16)> {
+            let (a, b) = s.split_once('x')?;
+            Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+        };
+        match kind {
+            PromptKind::Resize => {
+                let Some((x, y)) = pair(input) else {
+                    log::warn!("Invalid size: {input:?}");
+                    return Ok(());
+                };
+                if self.canvas.resize(UVec { x, y }) {
+                    self.undo_cursor_pos.push(self.cursor);
+                }
+                self.redo_cursor_pos.clear();
+            }
+            PromptKind::Table => {
+                let size = input
+                    .split_once(' ')
+                    .and_then(|(count, cell)| Some((pair(count)?, pair(cell)?)));
+                let Some(((rows, cols), (w, h))) = size else {
+                    log::warn!("Invalid table: {input:?}");
+                    return Ok(());
+                };
+                let table = Table {
+                    glyphs: self.rect_glyphs(),
+                    ..Table::new(self.cursor, rows, cols, w, h)
+                };
+                if table.bounds().is_none() {
+                    let msg = format!("table {input} is too big");
+                    log::warn!("Not drawing table: {msg}");
+                    self.error = Some(msg);
+                    return Ok(());
+                }
+                log::debug!("Drawing table: {table:?}");
+                if self.canvas.edit(table.edits().into_iter()) {
+                    self.undo_cursor_pos.push(self.cursor);
+                }
+                self.redo_cursor_pos.clear();
+            }
+            PromptKind::Goto => {
+                let Ok(target) = input.parse::<UVec>() else {
+                    log::warn!("Invalid coordinate: {input:?}");
+                    return Ok(());
+                };
+                let dim = self.canvas.dimensions();
+                self.cursor = UVec {
+                    x: target.x.min(dim.x.saturating_sub(1)),
+                    y: target.y.min(dim.y.saturating_sub(1)),
+                };
+                log::debug!("Moved cursor to {:?}", self.cursor);
+            }
+            PromptKind::Window(r) => {
+                log::debug!("Drawing window {r:?} titled {input:?}");
+                if self.canvas.edit(r.window_edits(input).into_iter()) {
+                    self.undo_cursor_pos.push(r.top_left);
+                }
+                self.redo_cursor_pos.clear();
+                self.last_edit_cursor_pos = self.cursor;
+            }
+            PromptKind::ConfirmNew => {
+                if input.trim().eq_ignore_ascii_case("y") {
+                    self.new_document();
+                }
+            }
+            PromptKind::ConfirmQuit => {
+                if input.trim().eq_ignore_ascii_case("y") {
+                    self.apply_action(Action::Quit)?;
+                }
+            }
+            PromptKind::SaveAs => {
+                if input.is_empty() {
+                    log::warn!("No path given, not saving");
+                    return Ok(());
+                }
+                self.path = Some(input.into());
+                self.apply_action(Action::Save)?;
+            }
         }
         Ok(())
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         log::trace!("Handling key {key:?} in mode {:?}", self.mode);
+        self.error = None;
+
+        if let Mode::Prompt(p) = &mut self.mode {
+            match key.code {
+                KeyCode::Backspace => {
+                    p.input.pop();
+                    return Ok(());
+                }
+                KeyCode::Char(c) if key.modifiers.is_empty() => {
+                    p.input.push(c);
+                    return Ok(());
+                }
+                KeyCode::Enter if key.modifiers.is_empty() => {
+                    let Mode::Prompt(p) = std::mem::take(&mut self.mode) else {
+                        unreachable!();
+                    };
+                    log::debug!("Submitting prompt {p:?}");
+                    return self.submit(p.kind, &p.input);
+                }
+                _ => {}
+            }
+        }
 
         if let Mode::Text(s) = &mut self.mode {
+            // the direction the cursor advances as text is typed
+            let (dx, dy) = if s.vertical { (0, 1) } else { (1, 0) };
             match key.code {
                 KeyCode::Backspace => {
                     let c = s.text.pop();
                     log::debug!("Popped {c:?} from {s:?}");
-                    if c.is_some() {
-                        self.move_cursor(-1, 0);
+                    if c == Some('\n') || (c.is_some() && s.wrap_width.is_some()) {
+                        // join onto the end of the previous line, or follow
+                        // words wrapping back
+                        let d = s.end() - self.cursor;
+                        self.move_cursor(d.x, d.y);
+                    } else if c.is_some() {
+                        self.move_cursor(-dx, -dy);
                     }
                     return Ok(());
                 }
                 KeyCode::Char(c) if key.modifiers.is_empty() => {
-                    log::debug!("Appending {c} to {s:?}");
-                    s.text.push(c);
-                    self.move_cursor(1, 0);
+                    if self.config.text_skip_occupied {
+                        self.skip_occupied(dx, dy);
+                    }
+                    if let Mode::Text(s) = &mut self.mode {
+                        log::debug!("Appending {c} to {s:?}");
+                        s.text.push(c);
+                    }
+                    if !self.follow_wrapped_text() {
+                        self.move_cursor(dx, dy);
+                    }
                     return Ok(());
                 }
-                KeyCode::Enter if key.modifiers.is_empty() => {
-                    log::debug!("Appending newline to {s:?}");
-                    let len = s.text.lines().last().map(|l| l.len()).unwrap_or(0);
-                    s.text.push('\n');
-                    self.move_cursor(-(len as i16), 1);
-                    return Ok(());
+                // unless enter is bound to something else for text
+                KeyCode::Enter if key.modifiers.is_empty() && self.enter_adds_line() => {
+                    return self.apply_action(Action::TextAddLine);
                 }
                 _ => {}
             }
         }
 
+        if let Mode::Paint(p) = &mut self.mode {
+            let plain = key
+                .modifiers
+                .difference(event::KeyModifiers::SHIFT)
+                .is_empty();
+            if let (None, KeyCode::Char(c), true) = (p.char, key.code, plain) {
+                log::debug!("Painting with {c}");
+                p.char = Some(c);
+                p.toggle(self.cursor);
+                return Ok(());
+            }
+        }
+
         let Some(bound) = self.binds.get(&key) else {
             log::trace!("Mapped key to no action");
             return Ok(());
         };
         log::trace!("Mapped key to {bound:?}");
 
-        match bound {
-            crate::config::Binding::Single(s) => self.apply_action(s.clone())?,
-            crate::config::Binding::Multi(m) => {
-                for action in m.clone() {
-                    self.apply_action(action)?;
-                }
+        let actions = match bound {
+            crate::config::Binding::Single(s) => vec![s.clone()],
+            crate::config::Binding::Multi(m) => m.clone(),
+        };
+        for action in actions {
+            let summary = self.config.log_actions.then(|| format!("{action:?}"));
+            self.dispatch(action)?;
+            if let Some(summary) = summary {
+                self.log_action(&summary);
             }
         }
         Ok(())
     }
+
+    // With text wrapping, move the cursor to where the next char goes, as
+    // typing may carry a word onto the next line. Returns false otherwise.
+    fn follow_wrapped_text(&mut self) -> bool {
+        let end = match &self.mode {
+            Mode::Text(t) if t.wrap_width.is_some() => t.end(),
+            _ => return false,
+        };
+        let d = end - self.cursor;
+        self.move_cursor(d.x, d.y);
+        true
+    }
+
+    // Whether enter starts a new line of text, as it does unless bound to
+    // something other than confirming or adding a line.
+    fn enter_adds_line(&self) -> bool {
+        match self.binds.get(&KeyCode::Enter.into()) {
+            None => true,
+            Some(crate::config::Binding::Single(action)) => matches!(
+                action,
+                Action::TextAddLine | Action::ConfirmShape | Action::ExitMode
+            ),
+            Some(crate::config::Binding::Multi(_)) => false,
+        }
+    }
+
+    // Log one line describing an action and the state it left behind.
+    fn log_action(&self, action: &str) {
+        log::info!(
+            target: "boxt::action",
+            "{action} -> mode={} cursor={},{}",
+            self.mode.name(),
+            self.cursor.x,
+            self.cursor.y
+        );
+    }
+
+    // Draw the canvas shrunk to fit a third of the width and half the height of
+    // area, in its top right corner, with the blocks on screen reversed.
+    fn render_minimap(&self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
+        let max = UVec {
+            x: (area.width / 3).max(1),
+            y: (area.height / 2).max(1),
+        };
+        let dim = self.canvas.dimensions();
+        let scale = UVec {
+            x: dim.x.div_ceil(max.x).max(1),
+            y: dim.y.div_ceil(max.y).max(1),
+        };
+        let blocks = self.canvas.downscale(scale);
+        let width = blocks.first().map_or(0, |row| row.len()) as u16;
+        let Some(left) = (area.x + area.width).checked_sub(width) else {
+            return;
+        };
+        log::trace!(
+            "Drawing {width}x{} minimap at scale {scale:?}",
+            blocks.len()
+        );
+
+        let on_screen =
+            |b: u16, s: u16, start: u16, len: u16| b * s < start + len && start < (b + 1) * s;
+        for (y, row) in blocks.iter().enumerate().take(area.height as usize) {
+            for (x, filled) in row.iter().enumerate() {
+                let (x, y) = (x as u16, y as u16);
+                let mut style = Style::new().dim();
+                if on_screen(x, scale.x, self.viewport.x, area.width)
+                    && on_screen(y, scale.y, self.viewport.y, area.height)
+                {
+                    style = style.reversed();
+                }
+                buf[(left + x, area.y + y)]
+                    .set_char(if *filled { '▪' } else { ' ' })
+                    .set_style(style);
+            }
+        }
+    }
 }
 
 impl Widget for &App {
@@ -336,7 +1694,7 @@ impl Widget for &App {
             " Quit ".into(),
             "<Q> ".blue().bold(),
         ]));
-        let block = Block::bordered()
+        let mut block = Block::bordered()
             .title(title.alignment(Alignment::Center))
             .title(
                 instructions
@@ -344,41 +1702,302 @@ impl Widget for &App {
                     .position(ratatui::widgets::block::Position::Bottom),
             )
             .border_set(ratatui::symbols::border::THICK);
+        let status = self.status();
+        if let Some(status) = &status {
+            block = block.title(Title::from(format!(" {status} ")).alignment(Alignment::Right));
+        }
 
-        // TODO: have separate scratch layer
-        let mut canvas = self.canvas.clone();
-
-        let mut style = ratatui::style::Style::default();
-        match &self.mode {
-            Mode::Normal => {}
+        // dim shapes that are not yet committed
+        let mut style = Style::new().dim();
+        let preview: Vec<Edit> = match &self.mode {
+            Mode::Normal | Mode::Select { .. } | Mode::Prompt(_) => vec![],
+            // show what is left once the region is cleared
+            Mode::Erase { anchor } => {
+                let (x1, x2) = (anchor.x.min(self.cursor.x), anchor.x.max(self.cursor.x));
+                let (y1, y2) = (anchor.y.min(self.cursor.y), anchor.y.max(self.cursor.y));
+                (y1..=y2)
+                    .map(|y| Edit::Right {
+                        start: UVec { x: x1, y },
+                        chars: vec![self.config.erase_char; (x2 - x1 + 1) as usize],
+                    })
+                    .collect()
+            }
             Mode::Rect(r) => {
                 log::debug!("Drawing rect: {r:?}");
-                canvas.edit(r.edits().into_iter());
+                let mut edits = r.edits();
+                edits.extend(r.shadow_edits(&self.canvas));
+                edits
             }
             Mode::Line(l) => {
                 log::debug!("Drawing line: {l:?}");
-                canvas.edit(l.edits().into_iter());
+                l.edits()
             }
             Mode::Text(t) => {
                 log::debug!("Drawing text: {t:?}");
-                canvas.edit(t.edits().into_iter());
+                t.edits()
+            }
+            Mode::Paint(p) => {
+                log::debug!("Drawing paint: {p:?}");
+                p.edits()
             }
             Mode::SelectRect {
                 original, current, ..
             } => {
                 log::debug!("Drawing selected rect: {current:?}");
-                canvas.edit(original.edits().into_iter().map(|e| e.erase()));
-                canvas.edit(current.edits().into_iter());
-                style = style.bold().fg(Color::Cyan);
+                style = style.patch(self.config.selection_style.style());
+                original
+                    .edits()
+                    .into_iter()
+                    .map(|e| e.erase(self.config.erase_char))
+                    .chain(current.edits())
+                    .collect()
+            }
+            Mode::SelectLine { original, current } => {
+                log::debug!("Drawing selected line: {current:?}");
+                style = style.patch(self.config.selection_style.style());
+                original
+                    .edits()
+                    .into_iter()
+                    .map(|e| e.erase(self.config.erase_char))
+                    .chain(current.edits())
+                    .collect()
+            }
+        };
+
+        if self.config.border {
+            block.render(area, buf);
+        } else if let Some(status) = status.filter(|_| area.height > 0) {
+            let line = ratatui::prelude::Rect::new(area.x, area.bottom() - 1, area.width, 1);
+            ratatui::text::Line::from(status)
+                .alignment(Alignment::Right)
+                .render(line, buf);
+        }
+        let inner = self.canvas_area(area);
+
+        // the distance of each row from the cursor, in the gutter to the left
+        let gutter = self.gutter_width(inner.height);
+        if gutter > 0 && inner.x >= area.x + gutter {
+            for i in 0..inner.height {
+                let n = (self.viewport.y + i).abs_diff(self.cursor.y);
+                buf.set_string(
+                    inner.x - gutter,
+                    inner.y + i,
+                    format!("{n:>w$} ", w = gutter as usize - 1),
+                    Style::new().dim(),
+                );
+            }
+        }
+
+        // lines and rects join what they cross, as they will once confirmed
+        let join = matches!(
+            self.mode,
+            Mode::Rect(_) | Mode::Line(_) | Mode::SelectRect { .. } | Mode::SelectLine { .. }
+        );
+        render_canvas(
+            &self.canvas,
+            &preview,
+            join,
+            style,
+            self.viewport,
+            inner,
+            buf,
+        );
+
+        if self.config.show_whitespace {
+            let dim = self.canvas.dimensions();
+            let area = self.to_screen(ratatui::prelude::Rect::new(0, 0, dim.x, dim.y), inner);
+            for pos in area.positions() {
+                let cell = &mut buf[pos];
+                if cell.symbol() == " " {
+                    cell.set_char('·').set_style(Style::new().dim());
+                }
+            }
+        }
+
+        // mark where the shape being drawn was started
+        let anchor = match &self.mode {
+            Mode::Rect(r) => Some(r.top_left),
+            Mode::Line(l) => Some(l.start),
+            _ => None,
+        };
+        if let Some(anchor) = anchor {
+            let marker = self.config.anchor_marker;
+            let area = self.to_screen(ratatui::prelude::Rect::new(anchor.x, anchor.y, 1, 1), inner);
+            if !area.is_empty() {
+                let cell = &mut buf[(area.x, area.y)];
+                if let Some(c) = marker.char {
+                    cell.set_char(c);
+                }
+                cell.set_style(match marker.style {
+                    MarkerStyle::Plain => Style::new(),
+                    MarkerStyle::Bold => Style::new().bold(),
+                    MarkerStyle::Underlined => Style::new().underlined(),
+                    MarkerStyle::Reversed => Style::new().reversed(),
+                });
+            }
+        }
+
+        if let Mode::Select { anchor } | Mode::Erase { anchor } = self.mode {
+            let (x1, x2) = (anchor.x.min(self.cursor.x), anchor.x.max(self.cursor.x));
+            let (y1, y2) = (anchor.y.min(self.cursor.y), anchor.y.max(self.cursor.y));
+            let region = ratatui::prelude::Rect::new(x1, y1, x2 - x1 + 1, y2 - y1 + 1);
+            buf.set_style(self.to_screen(region, inner), Style::new().reversed());
+        }
+
+        if self.config.highlight_cursor {
+            let cursor = ratatui::prelude::Rect::new(self.cursor.x, self.cursor.y, 1, 1);
+            buf.set_style(self.to_screen(cursor, inner), Style::new().reversed());
+        }
+
+        if self.config.minimap {
+            self.render_minimap(inner, buf);
+        }
+    }
+}
+
+// Draw the canvas into area cell by cell, starting from the offset cell, with
+// the preview edits on top in the given style, joining lines they cross if
+// join is set.
+fn render_canvas(
+    canvas: &Canvas,
+    preview: &[Edit],
+    join: bool,
+    style: Style,
+    offset: UVec,
+    area: ratatui::prelude::Rect,
+    buf: &mut Buffer,
+) {
+    let rows = canvas
+        .rows()
+        .iter()
+        .skip(offset.y as usize)
+        .take(area.height as usize);
+    for (y, row) in rows.enumerate() {
+        let row = row.iter().skip(offset.x as usize).take(area.width as usize);
+        for (x, c) in row.enumerate() {
+            let cell = buf[(area.x + x as u16, area.y + y as u16)].set_char(*c);
+            let point = UVec {
+                x: offset.x + x as u16,
+                y: offset.y + y as u16,
+            };
+            match canvas.color(point) {
+                Some(ansi::Color::Indexed(i)) => cell.set_fg(Color::Indexed(i)),
+                Some(ansi::Color::Rgb(r, g, b)) => cell.set_fg(Color::Rgb(r, g, b)),
+                None => cell,
+            };
+        }
+    }
+
+    for e in preview {
+        for (p, c) in e.cells().zip(e.chars()) {
+            if p.x < offset.x || p.y < offset.y {
+                continue;
+            }
+            let (x, y) = (p.x - offset.x, p.y - offset.y);
+            if x >= area.width || y >= area.height {
+                continue;
             }
+            let cell = &mut buf[(area.x + x, area.y + y)];
+            let under = cell.symbol().chars().next().unwrap_or(' ');
+            let c = if join {
+                Line::junction(under, c).unwrap_or(c)
+            } else {
+                c
+            };
+            cell.set_char(c).set_style(style);
         }
+    }
+}
+
+fn buf_string(buf: &Buffer) -> String {
+    buf.content
+        .chunks(buf.area.width as usize)
+        .map(|line| {
+            line.iter()
+                .map(|cell| cell.symbol().to_string())
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The editor without a terminal, driven by synthetic key events.
+///
+/// ```
+/// use boxt::{config::Config, tui::HeadlessApp};
+/// use crossterm::event::KeyCode;
+///
+/// let mut app = HeadlessApp::new(Config::default(), None).unwrap();
+/// // draw a rect from the cursor, then confirm it
+/// for c in "rdds".chars() {
+///     app.key(KeyCode::Char(c).into()).unwrap();
+/// }
+/// app.key(KeyCode::Enter.into()).unwrap();
+///
+/// let canvas = app.canvas().to_string();
+/// let rows: Vec<_> = canvas.lines().take(3).map(str::trim_end).collect();
+/// assert_eq!(rows, ["+--+", "|  |", "+--+"]);
+/// ```
+pub struct HeadlessApp(App);
+
+impl HeadlessApp {
+    /// Open the diagram at path, or a new canvas if there is none.
+    pub fn new(config: Config, path: Option<std::path::PathBuf>) -> Result<Self> {
+        Ok(Self(App::new(config, path)?))
+    }
+
+    /// Handle a key as if it were pressed in the terminal.
+    pub fn key(&mut self, key: KeyEvent) -> Result<()> {
+        self.0.handle_key_event(key)
+    }
+
+    /// Perform an action directly, whatever keys are bound to it.
+    ///
+    /// ```
+    /// use boxt::{config::{Action, Config}, tui::HeadlessApp};
+    ///
+    /// let mut app = HeadlessApp::new(Config::default(), None).unwrap();
+    /// for action in [
+    ///     Action::DrawRect,
+    ///     Action::MoveCursorRight,
+    ///     Action::MoveCursorRight,
+    ///     Action::MoveCursorDown,
+    ///     Action::ExitMode,
+    /// ] {
+    ///     app.apply_action(action).unwrap();
+    /// }
+    ///
+    /// let canvas = app.canvas().to_string();
+    /// let rows: Vec<_> = canvas.lines().take(3).map(str::trim_end).collect();
+    /// assert_eq!(rows, ["+--+", "|  |", "+--+"]);
+    /// ```
+    pub fn apply_action(&mut self, action: Action) -> Result<()> {
+        self.0.dispatch(action)
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.0.canvas
+    }
+
+    pub fn cursor(&self) -> UVec {
+        self.0.cursor
+    }
+
+    /// Whether a quit action has been handled.
+    pub fn exited(&self) -> bool {
+        self.0.exit
+    }
 
-        let text = ratatui::text::Text::styled(canvas.to_string(), style);
-        Paragraph::new(text).block(block).render(area, buf);
+    /// Draw the editor as it would appear on a terminal of the given size.
+    pub fn render_to_string(&self, width: u16, height: u16) -> String {
+        let mut buf = Buffer::empty(ratatui::prelude::Rect::new(0, 0, width, height));
+        (&self.0).render(buf.area, &mut buf);
+        buf_string(&buf)
     }
 }
 
-pub fn start(config: Config, path: std::path::PathBuf) -> Result<()> {
+pub fn start(config: Config, path: Option<std::path::PathBuf>) -> Result<()> {
     let mut terminal = ratatui::init();
     terminal.clear()?;
 
@@ -395,6 +2014,7 @@ mod tests {
     use event::KeyModifiers;
     use insta::assert_snapshot;
     use pretty_assertions::assert_eq;
+    use ratatui::widgets::Paragraph;
 
     struct Test {
         app: App,
@@ -407,17 +2027,25 @@ mod tests {
         }
 
         fn load(lines: &[&str]) -> Test {
+            Test::with_config(Config::default(), lines)
+        }
+
+        fn with_config(config: Config, lines: &[&str]) -> Test {
             let mut tmp = tempfile::NamedTempFile::new().unwrap();
             tmp.write_all(lines.join("\n").as_bytes()).unwrap();
             tmp.flush().unwrap();
-            let app = App::new(Config::default(), tmp.path().to_path_buf()).unwrap();
+            let app = App::new(config, Some(tmp.path().to_path_buf())).unwrap();
             Test { app, tmp }
         }
 
         fn render(&self) -> String {
+            buf_string(&self.render_buf())
+        }
+
+        fn render_buf(&self) -> Buffer {
             let mut buf = Buffer::empty(layout::Rect::new(0, 0, 32, 8));
             self.app.render(buf.area, &mut buf);
-            buf_string(&buf)
+            buf
         }
 
         fn key(&mut self, key: KeyCode) {
@@ -430,17 +2058,12 @@ mod tests {
         }
     }
 
-    fn buf_string(buf: &Buffer) -> String {
-        buf.content
-            .chunks(buf.area.width as usize)
-            .map(|line| {
-                line.iter()
-                    .map(|cell| cell.symbol().to_string())
-                    .collect::<Vec<_>>()
-                    .join("")
-            })
-            .collect::<Vec<_>>()
-            .join("\n")
+    impl Drop for Test {
+        fn drop(&mut self) {
+            // don't leave sessions behind in the temp dir
+            let _ = std::fs::remove_file(Session::path(self.tmp.path()));
+            let _ = std::fs::remove_file(session::history_path(self.tmp.path()));
+        }
     }
 
     fn input(app: &mut App, keys: &[char]) {
@@ -455,13 +2078,47 @@ mod tests {
         assert_snapshot!(test.render());
     }
 
+    #[test]
+    fn test_tui_render_borderless() {
+        let config = Config {
+            border: false,
+            ..Default::default()
+        };
+        let test = Test::with_config(config, &["+--+", "|ab|", "+--+"]);
+        assert_snapshot!(test.render());
+    }
+
+    #[test]
+    fn test_tui_borderless_status() {
+        let config = Config {
+            border: false,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["+--+", "|ab|", "+--+"]);
+        test.input("g12");
+        let buf = test.render_buf();
+        let bottom: String = (0..32).map(|x| buf[(x, 7)].symbol()).collect();
+        assert_eq!(bottom.trim(), "go to (x,y): 12");
+    }
+
+    #[test]
+    fn test_tui_relative_numbers() {
+        let config = Config {
+            relative_numbers: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["abc", "def", "ghi", "jkl", "mno", "pqr"]);
+        test.input("ss");
+        assert_snapshot!(test.render());
+    }
+
     #[test]
     fn test_tui_draw_rect() {
         let mut test = Test::new();
 
         // Draw one rect and confirm it
         test.input("rsd");
-        test.key(KeyCode::Esc);
+        test.key(KeyCode::Enter);
 
         // Start drawing another rect
         test.input("ddrsd");
@@ -469,13 +2126,26 @@ mod tests {
         assert_snapshot!(test.render());
     }
 
+    #[test]
+    fn test_tui_anchor_marker() {
+        let mut config = Config::default();
+        config.anchor_marker.char = Some('x');
+        let mut test = Test::with_config(config, &[]);
+
+        test.input("rdd");
+        assert_snapshot!(test.render());
+        let buf = test.render_buf();
+        assert!(buf[(1, 1)].modifier.contains(Modifier::UNDERLINED));
+        assert!(!buf[(4, 2)].modifier.contains(Modifier::UNDERLINED));
+    }
+
     #[test]
     fn test_tui_draw_line() {
         let mut test = Test::new();
 
         // Draw a line and confirm it
         test.input("lddsss");
-        test.key(KeyCode::Esc);
+        test.key(KeyCode::Enter);
 
         // Draw a unconfirmed line with multiple points
         test.input("sdddddlwwaa dd ww");
@@ -507,33 +2177,514 @@ mod tests {
     }
 
     #[test]
-    fn test_tui_load() {
-        let test = Test::load(&["  --  ", " hello ", " _   _ ", ""]);
-        assert_snapshot!(test.render());
+    fn test_tui_text_backspace_join() {
+        let mut test = Test::new();
+
+        test.input("sdiab");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 2 });
+
+        // backspace at the start of a line joins it to the line above
+        test.key(KeyCode::Backspace);
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 1 });
+        test.input("c");
+        match &test.app.mode {
+            Mode::Text(t) => assert_eq!(t.text, "abc"),
+            mode => panic!("Not in text mode: {mode:?}"),
+        }
+        assert_eq!(test.app.cursor, UVec { x: 4, y: 1 });
     }
 
     #[test]
-    fn test_tui_save() {
-        let mut test = Test::new();
-
-        // Draw some text and confirm it
-        test.input("isave this");
-        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+    fn test_tui_double_esc_quit() {
+        let config = || Config {
+            double_esc_quit: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config(), &["abc"]);
+        test.key(KeyCode::Esc);
+        assert!(!test.app.exit);
+        test.key(KeyCode::Esc);
+        assert!(test.app.exit);
 
-        // Save
-        test.app
-            .handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
-            .unwrap();
+        // another action in between starts over
+        let mut test = Test::with_config(config(), &["abc"]);
+        test.key(KeyCode::Esc);
+        test.input("d");
+        test.key(KeyCode::Esc);
+        assert!(!test.app.exit);
 
-        let actual = std::fs::read_to_string(test.tmp.path()).unwrap();
-        assert_snapshot!(actual);
+        // unsaved changes must be discarded first
+        let mut test = Test::with_config(config(), &["abc"]);
+        test.input("x");
+        test.key(KeyCode::Esc);
+        test.key(KeyCode::Esc);
+        assert!(!test.app.exit);
+        assert_eq!(test.app.status(), Some("discard changes? (y/n): ".into()));
+        test.input("y");
+        test.key(KeyCode::Enter);
+        assert!(test.app.exit);
     }
 
     #[test]
-    fn test_tui_delete() {
-        let mut test = Test::load(&["delete me"]);
-        test.input("xddx");
-        assert_snapshot!(test.render());
+    fn test_tui_mode_switch() {
+        let mut test = Test::load(&[]);
+        test.input("rdl");
+        assert!(matches!(test.app.mode, Mode::Line(_)));
+        assert_eq!(test.app.canvas.to_string(), "");
+
+        let config = Config {
+            mode_switch: ModeSwitch::Confirm,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+        test.input("rdl");
+        assert!(matches!(test.app.mode, Mode::Line(_)));
+        assert_eq!(test.app.canvas.to_string(), "+-+\n+-+");
+    }
+
+    #[test]
+    fn test_tui_skip_empty_lines() {
+        let mut test = Test::load(&[]);
+        test.input("l");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.to_string(), "+");
+
+        let config = Config {
+            skip_empty_lines: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+        test.input("l");
+        test.key(KeyCode::Enter);
+        assert!(matches!(test.app.mode, Mode::Normal));
+        assert_eq!(test.app.canvas.to_string(), "");
+        assert!(test.app.undo_cursor_pos.is_empty());
+
+        // a line with length is still drawn
+        test.input("ldd");
+        test.key(KeyCode::Enter);
+        assert_ne!(test.app.canvas.to_string(), "");
+        assert_eq!(test.app.undo_cursor_pos.len(), 1);
+    }
+
+    #[test]
+    fn test_tui_line_order() {
+        let row = |test: &Test, y: usize| {
+            let s = test.app.canvas.to_string();
+            s.lines().nth(y).unwrap_or_default().trim_end().to_string()
+        };
+
+        let mut test = Test::load(&[]);
+        test.input("lddss");
+        assert!(matches!(
+            test.app.mode,
+            Mode::Line(Line { mirror: false, .. })
+        ));
+        test.key(KeyCode::Enter);
+        assert_eq!(row(&test, 1), "|");
+
+        let config = Config {
+            line_order: LineOrder::HorizontalFirst,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+        test.input("lddss");
+        assert!(matches!(
+            test.app.mode,
+            Mode::Line(Line { mirror: true, .. })
+        ));
+        test.key(KeyCode::Enter);
+        assert_eq!(row(&test, 1), "  |");
+
+        // each segment of a line starts in the configured order too
+        test.input("l ");
+        assert!(matches!(
+            test.app.mode,
+            Mode::Line(Line { mirror: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_cancel_rect() {
+        let mut test = Test::new();
+
+        test.input("ddrds");
+        test.key(KeyCode::Enter);
+        let before = test.app.canvas.to_string();
+
+        // start another rect, then discard it
+        test.input("rdd");
+        test.key(KeyCode::Esc);
+        assert!(matches!(test.app.mode, Mode::Normal));
+        assert_eq!(test.app.canvas.to_string(), before);
+        assert_snapshot!(test.render());
+    }
+
+    #[test]
+    fn test_cancel_selection() {
+        let mut test = Test::new();
+
+        test.input("rdds");
+        test.key(KeyCode::Enter);
+        let before = test.app.canvas.to_string();
+
+        // moving a selected rect is discarded too
+        test.input("awmss");
+        assert!(matches!(test.app.mode, Mode::SelectRect { .. }));
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string(), before);
+    }
+
+    #[test]
+    fn test_tui_draw_text_vertical() {
+        let mut test = Test::new();
+
+        test.input("IABC");
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 3 });
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 0 });
+        test.input("de");
+        test.key(KeyCode::Esc);
+
+        assert_eq!(test.app.canvas.to_string(), "Ad\nBe\nC ");
+    }
+
+    #[test]
+    fn test_tui_text_skip_occupied() {
+        let config = Config {
+            text_skip_occupied: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["  |", "  |"]);
+
+        test.input("iabcd");
+        assert_eq!(test.app.cursor, UVec { x: 5, y: 0 });
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string(), "ab|cd\n  |  ");
+
+        // without the option, text is drawn over the line
+        let mut test = Test::load(&["  |", "  |"]);
+        test.input("iabcd");
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string(), "abcd\n  | ");
+    }
+
+    #[test]
+    fn test_tui_text_wrap() {
+        let config = Config {
+            text_wrap_width: Some(5),
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+
+        // the cursor follows a word onto the next line
+        test.input("iab cd ef");
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+        test.key(KeyCode::Backspace);
+        test.key(KeyCode::Backspace);
+        test.key(KeyCode::Backspace);
+        assert_eq!(test.app.cursor, UVec { x: 5, y: 0 });
+        test.input(" g");
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 1 });
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string(), "ab cd\ng    ");
+    }
+
+    #[test]
+    fn test_tui_snap_lines() {
+        let config = Config {
+            snap_lines: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+
+        test.input("ldddddds");
+        let Mode::Line(l) = &test.app.mode else {
+            panic!("Not drawing a line: {:?}", test.app.mode);
+        };
+        assert_eq!(l.end, UVec { x: 6, y: 0 });
+        // the cursor is not constrained
+        assert_eq!(test.app.cursor, UVec { x: 6, y: 1 });
+
+        test.input("sssaa");
+        let Mode::Line(l) = &test.app.mode else {
+            panic!("Not drawing a line: {:?}", test.app.mode);
+        };
+        assert_eq!(l.end, UVec { x: 4, y: 4 });
+    }
+
+    #[test]
+    fn test_acceleration() {
+        let mut accel = Acceleration::default();
+        let start = std::time::Instant::now();
+        let at = |ms| start + std::time::Duration::from_millis(ms);
+
+        let steps: Vec<_> = (0..10).map(|i| accel.step((1, 0), at(i * 50))).collect();
+        assert_eq!(steps, vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3]);
+
+        // changing direction starts over
+        assert_eq!(accel.step((0, 1), at(500)), 1);
+        assert_eq!(accel.step((0, 1), at(550)), 1);
+
+        // so does pausing
+        assert_eq!(accel.step((0, 1), at(1000)), 1);
+
+        let steps: Vec<_> = (0..24)
+            .map(|i| accel.step((-1, 0), at(2000 + i * 10)))
+            .collect();
+        assert_eq!(steps.last(), Some(&ACCEL_MAX));
+    }
+
+    #[test]
+    fn test_tui_new_document() {
+        let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        let mut test = Test::load(&["abc"]);
+        let before = test.app.canvas.to_string();
+
+        // nothing to lose yet
+        test.app.handle_key_event(ctrl_n).unwrap();
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 32, y: 32 });
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), before);
+
+        test.input("idef");
+        test.key(KeyCode::Esc);
+        let drawn = test.app.canvas.to_string();
+
+        // declining keeps the changes
+        test.app.handle_key_event(ctrl_n).unwrap();
+        assert_eq!(test.app.status().unwrap(), "discard changes? (y/n): ");
+        test.input("n");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.to_string(), drawn);
+
+        test.app.handle_key_event(ctrl_n).unwrap();
+        test.input("y");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 32, y: 32 });
+        assert!(test.app.canvas.to_string().trim().is_empty());
+        assert_eq!(test.app.path, None);
+
+        // the blank document is not a change to discard
+        test.app.handle_key_event(ctrl_n).unwrap();
+        assert_eq!(test.app.status(), None);
+
+        test.input("uu");
+        assert_eq!(test.app.canvas.to_string(), drawn);
+    }
+
+    #[test]
+    fn test_tui_log_actions() {
+        let config = Config {
+            log_actions: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+        crate::test_log::init();
+        crate::test_log::take("boxt::action");
+
+        test.input("dr");
+        test.key(KeyCode::Esc);
+        // one line for each of the four moves
+        test.key(KeyCode::Char('S'));
+
+        assert_eq!(
+            crate::test_log::take("boxt::action"),
+            vec![
+                "MoveCursorRight -> mode=normal cursor=1,0",
+                "DrawRect -> mode=rect cursor=2,1",
+                "CancelShape -> mode=normal cursor=2,1",
+                "MoveCursorDown -> mode=normal cursor=2,2",
+                "MoveCursorDown -> mode=normal cursor=2,3",
+                "MoveCursorDown -> mode=normal cursor=2,4",
+                "MoveCursorDown -> mode=normal cursor=2,5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tui_corner_snap() {
+        let config = Config {
+            corner_snap: 1,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+
+        test.input("rdds");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 2 });
+
+        // one cell right of the bottom right corner
+        test.input("dr");
+        let Mode::Rect(r) = &test.app.mode else {
+            panic!("Not drawing a rect: {:?}", test.app.mode);
+        };
+        assert_eq!(r.top_left, UVec { x: 3, y: 2 });
+        assert_eq!(test.app.cursor, UVec { x: 4, y: 3 });
+    }
+
+    #[test]
+    fn test_tui_load() {
+        let test = Test::load(&["  --  ", " hello ", " _   _ ", ""]);
+        assert_snapshot!(test.render());
+    }
+
+    #[test]
+    fn test_tui_save() {
+        let mut test = Test::new();
+
+        // Draw some text and confirm it
+        test.input("isave this");
+        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+
+        // Save
+        test.app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+
+        let actual = std::fs::read_to_string(test.tmp.path()).unwrap();
+        assert_snapshot!(actual);
+    }
+
+    #[test]
+    fn test_tui_save_session() {
+        let mut test = Test::load(&["abcd", "efgh", "ijkl"]);
+        test.input("dds");
+        test.app.viewport = UVec { x: 1, y: 1 };
+        test.app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+
+        let app = App::new(Config::default(), Some(test.tmp.path().to_path_buf())).unwrap();
+        assert_eq!(app.cursor, UVec { x: 2, y: 1 });
+        assert_eq!(app.viewport, UVec { x: 1, y: 1 });
+
+        // a cursor given on the command line wins over the session
+        let config = Config {
+            cursor: Some(UVec { x: 3, y: 2 }),
+            ..Default::default()
+        };
+        let app = App::new(config, Some(test.tmp.path().to_path_buf())).unwrap();
+        assert_eq!(app.cursor, UVec { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn test_tui_persist_undo() {
+        let config = || Config {
+            persist_undo: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config(), &["abcd"]);
+        test.input("xddx");
+        test.app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+
+        let mut app = App::new(config(), Some(test.tmp.path().to_path_buf())).unwrap();
+        assert_eq!(app.canvas.to_string(), " b d");
+        input(&mut app, &['u']);
+        assert_eq!(app.canvas.to_string(), " bcd");
+        input(&mut app, &['u']);
+        assert_eq!(app.canvas.to_string(), "abcd");
+
+        // without the option, history is neither saved nor loaded
+        let mut app = App::new(Config::default(), Some(test.tmp.path().to_path_buf())).unwrap();
+        input(&mut app, &['u']);
+        assert_eq!(app.canvas.to_string(), " b d");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tui_save_symlink() {
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        for setting in [SymlinkSave::Follow, SymlinkSave::Replace] {
+            let dir = tempfile::tempdir().unwrap();
+            let target = dir.path().join("target.txt");
+            let link = dir.path().join("link.txt");
+            std::fs::write(&target, "old").unwrap();
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+
+            let config = Config {
+                symlink_save: setting,
+                ..Default::default()
+            };
+            let mut app = App::new(config, Some(link.clone())).unwrap();
+            input(&mut app, &['x']);
+            app.handle_key_event(ctrl_s).unwrap();
+
+            let (is_link, target_content) = match setting {
+                SymlinkSave::Follow => (true, " ld"),
+                SymlinkSave::Replace => (false, "old"),
+            };
+            assert_eq!(link.is_symlink(), is_link, "{setting:?}");
+            assert_eq!(std::fs::read_to_string(&target).unwrap(), target_content);
+            assert_eq!(std::fs::read_to_string(&link).unwrap(), " ld");
+        }
+    }
+
+    #[test]
+    fn test_tui_save_scratch() {
+        let mut app = App::new(Config::default(), None).unwrap();
+        assert_eq!(app.canvas.dimensions(), UVec { x: 32, y: 32 });
+
+        input(&mut app, &['i', 'h', 'i']);
+        app.handle_key_event(KeyCode::Esc.into()).unwrap();
+
+        // the first save asks where to save to
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        app.handle_key_event(ctrl_s).unwrap();
+        assert_eq!(app.status(), Some("save as: ".into()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scratch.txt");
+        let chars: Vec<_> = path.to_str().unwrap().chars().collect();
+        input(&mut app, &chars);
+        app.handle_key_event(KeyCode::Enter.into()).unwrap();
+        assert_eq!(app.path, Some(path.clone()));
+
+        let actual = std::fs::read_to_string(&path).unwrap();
+        assert!(actual.starts_with("hi "));
+    }
+
+    #[test]
+    fn test_tui_save_create_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a").join("b").join("new.txt");
+        let save = |config: Config| {
+            let mut app = App::new(config, Some(path.clone())).unwrap();
+            input(&mut app, &['i', 'h', 'i']);
+            app.handle_key_event(KeyCode::Esc.into()).unwrap();
+            let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+            app.handle_key_event(ctrl_s).unwrap();
+            app
+        };
+
+        // the failure is reported, and the drawing kept to save elsewhere
+        let mut app = save(Config::default());
+        assert!(!app.exit);
+        assert!(app.status().unwrap().contains("create_dirs"));
+        assert!(app.canvas.to_string().starts_with("hi"));
+        assert!(!dir.path().join("a").exists());
+        input(&mut app, &['d']);
+        assert_eq!(app.status(), None);
+
+        let config = Config {
+            create_dirs: true,
+            ..Default::default()
+        };
+        let app = save(config);
+        assert_eq!(app.status(), None);
+        assert!(dir.path().join("a").join("b").is_dir());
+        let actual = std::fs::read_to_string(&path).unwrap();
+        assert!(actual.starts_with("hi"));
+    }
+
+    #[test]
+    fn test_tui_delete() {
+        let mut test = Test::load(&["delete me"]);
+        test.input("xddx");
+        assert_snapshot!(test.render());
     }
 
     #[test]
@@ -542,16 +2693,16 @@ mod tests {
 
         // Draw a few rects
         test.input("rsd");
-        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+        test.app.handle_key_event(KeyCode::Enter.into()).unwrap();
 
         test.input("rssddd");
-        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+        test.app.handle_key_event(KeyCode::Enter.into()).unwrap();
 
         test.input("ddrwwwa");
-        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+        test.app.handle_key_event(KeyCode::Enter.into()).unwrap();
 
         test.input("lsaa");
-        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+        test.app.handle_key_event(KeyCode::Enter.into()).unwrap();
 
         for _ in 0..4 {
             eprintln!("undo");
@@ -566,6 +2717,165 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tui_shift_canvas() {
+        let mut test = Test::load(&["ab", "-c"]);
+        test.app.apply_action(Action::ShiftCanvasRight).unwrap();
+        assert_eq!(test.app.canvas.to_string(), " ab\n -c");
+        assert!(test.app.canvas.rows().iter().all(|r| r[0] == ' '));
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 0 });
+
+        // content at the edge is not pushed off
+        test.app.apply_action(Action::ShiftCanvasUp).unwrap();
+        assert_eq!(test.app.canvas.to_string(), " ab\n -c");
+
+        test.app.apply_action(Action::ShiftCanvasDown).unwrap();
+        assert_eq!(test.app.canvas.to_string(), "   \n ab\n -c");
+
+        test.input("uu");
+        assert_eq!(test.app.canvas.to_string(), "ab\n-c");
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn test_tui_repeat_last() {
+        let mut test = Test::load(&["abcd"]);
+        // nothing to repeat yet
+        test.input(".");
+        assert_eq!(test.app.canvas.to_string(), "abcd");
+
+        test.input("xdd.");
+        assert_eq!(test.app.canvas.to_string(), " b d");
+        // undo is not repeated
+        test.input("ud.");
+        assert_eq!(test.app.canvas.to_string(), " bc ");
+    }
+
+    #[test]
+    fn test_tui_repeat_draw() {
+        let mut test = Test::load(&["      "; 5]);
+        test.input("rsd");
+        test.key(KeyCode::Enter);
+
+        // confirming is not repeated, so repeating starts another rect
+        test.input("dd.");
+        assert!(matches!(test.app.mode, Mode::Rect(_)));
+        test.input("s");
+        test.key(KeyCode::Enter);
+        assert_eq!(
+            test.app.canvas.to_string(),
+            ["+-+   ", "| |   ", "+-+ ++", "    ||", "    ++"].join("\n")
+        );
+    }
+
+    #[test]
+    fn test_tui_undo_redo_all() {
+        let mut test = Test::new();
+        test.input("rsd");
+        test.key(KeyCode::Enter);
+        test.input("rssddd");
+        test.key(KeyCode::Enter);
+        test.input("lsaa");
+        test.key(KeyCode::Enter);
+        let drawn = test.app.canvas.to_string();
+
+        test.app.apply_action(Action::UndoAll).unwrap();
+        assert_eq!(test.app.canvas.to_string().trim(), "");
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 0 });
+        assert!(test.app.undo_cursor_pos.is_empty());
+
+        test.app.apply_action(Action::RedoAll).unwrap();
+        assert_eq!(test.app.canvas.to_string(), drawn);
+        assert!(test.app.redo_cursor_pos.is_empty());
+
+        // back to one step at a time
+        test.input("u");
+        test.input("u");
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string().trim(), "");
+    }
+
+    #[test]
+    fn test_tui_paint() {
+        let mut test = Test::new();
+
+        test.input("sp*dddsa");
+        test.key(KeyCode::Enter);
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).take(3).collect();
+        assert_eq!(rows, vec!["", "****", "  **"]);
+
+        // the whole stroke is undone at once
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string().trim(), "");
+
+        // a modified key is an action, not a brush
+        test.input("p");
+        let ctrl_j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        test.app.handle_key_event(ctrl_j).unwrap();
+        let Mode::Paint(p) = &test.app.mode else {
+            panic!("Expected paint mode, got {:?}", test.app.mode);
+        };
+        assert_eq!(p.char, None);
+        let shift_x = KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT);
+        test.app.handle_key_event(shift_x).unwrap();
+        let Mode::Paint(p) = &test.app.mode else {
+            panic!("Expected paint mode, got {:?}", test.app.mode);
+        };
+        assert_eq!(p.char, Some('X'));
+    }
+
+    #[test]
+    fn test_tui_wrap_cursor() {
+        let config = Config {
+            wrap_cursor: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["0123456789"]);
+
+        test.input("a");
+        assert_eq!(test.app.cursor, UVec { x: 9, y: 0 });
+
+        // a line being drawn follows the cursor across the edge
+        test.input("ld");
+        let Mode::Line(l) = &test.app.mode else {
+            panic!("Expected line mode, got {:?}", test.app.mode);
+        };
+        assert_eq!(l.end, UVec { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn test_tui_mark_distance() {
+        let mut test = Test::new();
+        assert_eq!(test.app.status(), None);
+
+        test.input("dMdddss");
+        assert_eq!(test.app.status().unwrap(), "3x2 manhattan:5 chebyshev:3");
+
+        // setting the mark at the cursor clears it
+        test.input("dMM");
+        assert_eq!(test.app.status(), None);
+    }
+
+    #[test]
+    fn test_tui_move_rect_offset() {
+        let mut test = Test::load(&[
+            "                ",
+            "   +---+        ",
+            "   |   |        ",
+            "   |   |        ",
+            "   +---+        ",
+            "                ",
+        ]);
+
+        test.input("ssddddm");
+        assert_eq!(test.app.status().unwrap(), "5x4 offset 0,0");
+
+        test.input("sddddaa");
+        assert_snapshot!(test.app.status().unwrap());
+    }
+
     #[test]
     fn test_move_rect() {
         let mut test = Test::load(&[
@@ -581,7 +2891,7 @@ mod tests {
         let before = test.render();
 
         test.input("ssddddmsd");
-        test.app.handle_key_event(KeyCode::Esc.into()).unwrap();
+        test.app.handle_key_event(KeyCode::Enter.into()).unwrap();
 
         assert_snapshot!(test.render());
 
@@ -590,6 +2900,843 @@ mod tests {
         assert_eq!(test.render(), before);
     }
 
+    #[test]
+    fn test_move_rounded_rect() {
+        let mut test = Test::load(&[
+            "                ",
+            "   ╭───╮        ",
+            "   │   │        ",
+            "   │   │        ",
+            "   ╰───╯        ",
+            "                ",
+            "                ",
+        ]);
+
+        test.input("ssddddmsd");
+        test.app.handle_key_event(KeyCode::Enter.into()).unwrap();
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "",
+                "",
+                "    ╭───╮",
+                "    │   │",
+                "    │   │",
+                "    ╰───╯",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tui_sticky_tools() {
+        let config = Config {
+            sticky_tools: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+
+        test.input("rddss");
+        test.key(KeyCode::Enter);
+        assert!(matches!(test.app.mode, Mode::Rect(_)));
+
+        test.input("ddss");
+        test.key(KeyCode::Enter);
+
+        // nothing drawn since restarting, so exit
+        test.key(KeyCode::Enter);
+        assert!(matches!(test.app.mode, Mode::Normal));
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(
+            rows,
+            vec!["+--+", "|  |", "|  |", "+--+-+", "   | |", "   +-+"]
+        );
+    }
+
+    #[test]
+    fn test_tui_erase_lines() {
+        let mut test = Test::new();
+
+        // draw a labeled box
+        test.input("rddds");
+        test.key(KeyCode::Enter);
+        test.input("waaaifoo");
+        test.key(KeyCode::Esc);
+
+        test.input("amX");
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(rows, vec!["", " foo", ""]);
+    }
+
+    #[test]
+    fn test_tui_draw_double() {
+        let mut test = Test::new();
+
+        // toggle weight mid-rect, then draw a line crossing it
+        test.input("rtddds");
+        test.key(KeyCode::Enter);
+        test.input("waaltsss");
+        test.key(KeyCode::Enter);
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).take(5).collect();
+        assert_eq!(rows, vec!["╔═══╗", "║ + ║", "╚═╪═╝", "  |", "  +"]);
+    }
+
+    #[test]
+    fn test_nudge_line_end() {
+        let mut test = Test::new();
+
+        test.input("lddss");
+        test.key(KeyCode::Enter);
+        let before = test.app.canvas.to_string();
+
+        test.input("Ld");
+        test.key(KeyCode::Enter);
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(rows, vec!["+", "|", "+--+"]);
+
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), before);
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 2 });
+    }
+
+    #[test]
+    fn test_tui_text_arrows() {
+        let config = Config {
+            text_arrows: true,
+            theme: ThemeConfig::Builtin(BuiltinTheme::Unicode),
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+
+        test.input("ia --> b");
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string().trim_end(), "a ──▶ b");
+    }
+
+    #[test]
+    fn test_tui_line_points_undo() {
+        let mut test = Test::new();
+
+        // the final point adds nothing, so has nothing to undo
+        test.input("ldd ss ");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.undo_cursor_pos.len(), 2);
+
+        test.input("uu");
+        assert_eq!(test.app.canvas.to_string(), "");
+
+        let config = Config {
+            undo_merge_ms: 60_000,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+
+        // each segment overlaps the last at a corner
+        test.input("ldd ss ");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.undo_cursor_pos.len(), 1);
+
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), "");
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn test_tui_line_pop_point() {
+        let mut test = Test::new();
+
+        test.input("ldd ss dd ");
+        assert_eq!(test.app.line_segments.len(), 3);
+        assert_eq!(test.app.canvas.to_string(), "+-+  \n  |  \n  +-+");
+
+        test.key(KeyCode::Backspace);
+        assert_eq!(test.app.canvas.to_string(), "+-+\n  |\n  +");
+        assert_eq!(test.app.cursor, UVec { x: 4, y: 2 });
+        let Mode::Line(l) = &test.app.mode else {
+            panic!("Expected line mode, got {:?}", test.app.mode);
+        };
+        assert_eq!(l.start, UVec { x: 2, y: 2 });
+        assert_eq!(l.end, UVec { x: 4, y: 2 });
+
+        // the popped segment can be drawn again from the same anchor
+        test.input("d");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.to_string(), "+-+   \n  |   \n  +--+");
+    }
+
+    #[test]
+    fn test_tui_initial_cursor() {
+        let config = Config {
+            cursor: Some(UVec { x: 2, y: 1 }),
+            ..Default::default()
+        };
+        let test = Test::with_config(config, &["abcd", "efgh", "ijkl"]);
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+
+        // kept within the canvas
+        let config = Config {
+            cursor: Some(UVec { x: 9, y: 9 }),
+            ..Default::default()
+        };
+        let test = Test::with_config(config, &["abcd", "efgh", "ijkl"]);
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn test_tui_highlight_cursor() {
+        let config = Config {
+            highlight_cursor: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["abc"]);
+        test.input("d");
+
+        let buf = test.render_buf();
+        let reversed = |x, y| buf[(x, y)].modifier.contains(Modifier::REVERSED);
+        // +1 for the border
+        assert!(reversed(2, 1));
+        assert!(!reversed(1, 1));
+        assert!(!reversed(3, 1));
+        assert_snapshot!(buf_string(&buf));
+    }
+
+    #[test]
+    fn test_tui_preview_style() {
+        let mut test = Test::load(&["abc"]);
+        test.input("ddsrdds");
+
+        let buf = test.render_buf();
+        let mask = buf
+            .content
+            .chunks(buf.area.width as usize)
+            .map(|line| {
+                line.iter()
+                    .map(|cell| {
+                        if cell.modifier.contains(Modifier::DIM) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(mask);
+
+        // committed content is drawn plainly
+        test.key(KeyCode::Enter);
+        let buf = test.render_buf();
+        assert!(!buf[(3, 2)].modifier.contains(Modifier::DIM));
+        assert_eq!(buf[(3, 2)].symbol(), "+");
+    }
+
+    #[test]
+    fn test_tui_selection_style() {
+        let mut config = Config::default();
+        config.selection_style.fg = Some(Color::Magenta);
+        let mut test = Test::with_config(config, &["+--+", "|  |", "+--+"]);
+
+        test.input("dsm");
+        let buf = test.render_buf();
+        // the border is drawn in the selection style, inside the screen border
+        assert_eq!(buf[(1, 1)].fg, Color::Magenta);
+        assert!(buf[(1, 1)].modifier.contains(Modifier::BOLD));
+        assert_eq!(buf[(2, 2)].fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_tui_swap_anchor() {
+        let mut test = Test::new();
+
+        test.input("ddssrdd");
+        test.input("o");
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 2 });
+
+        // now the top left corner moves
+        test.input("aw");
+        test.key(KeyCode::Enter);
+
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(rows, vec!["", " +---+", " |   |", " +---+"]);
+    }
+
+    #[test]
+    fn test_render_canvas() {
+        let mut canvas = Canvas::new(12, 6);
+        canvas.edit(Rect::new(0, 0, 5, 3).edits().into_iter());
+        canvas.edit(Text::new(1, 1, "box").edits().into_iter());
+        canvas.edit(
+            Line::new(UVec { x: 8, y: 0 }, UVec { x: 8, y: 5 })
+                .edits()
+                .into_iter(),
+        );
+        let preview = Line {
+            glyphs: LineGlyphs::DOUBLE,
+            ..Line::new(UVec { x: 2, y: 4 }, UVec { x: 11, y: 4 })
+        }
+        .edits();
+        let style = Style::new().bold().fg(Color::Cyan);
+        let area = layout::Rect::new(1, 1, 10, 5);
+
+        // the original path, rendering the canvas as a string
+        let mut expected = Buffer::empty(layout::Rect::new(0, 0, 12, 8));
+        let mut scratch = canvas.clone();
+        scratch.draw(preview.clone().into_iter());
+        Paragraph::new(scratch.to_string()).render(area, &mut expected);
+        // only the preview is styled
+        for p in preview.iter().flat_map(|e| e.cells()) {
+            if p.x < area.width && p.y < area.height {
+                expected[(area.x + p.x, area.y + p.y)].set_style(style);
+            }
+        }
+
+        let mut actual = Buffer::empty(layout::Rect::new(0, 0, 12, 8));
+        render_canvas(
+            &canvas,
+            &preview,
+            true,
+            style,
+            UVec::default(),
+            area,
+            &mut actual,
+        );
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_tui_cursor_confinement() {
+        let config = Config {
+            cursor_confinement: CursorConfinement::Content,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["abc  ", "a    ", "     "]);
+
+        test.input("dddddd");
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 0 });
+        // moving onto a shorter row pulls the cursor back
+        test.input("s");
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 1 });
+        test.input("s");
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 2 });
+
+        // shapes may be drawn past the content
+        test.input("rddd");
+        assert_eq!(test.app.cursor, UVec { x: 4, y: 3 });
+    }
+
+    #[test]
+    fn test_tui_normal_enter() {
+        let config = Config {
+            normal_enter: NormalEnter::Newline,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["abc", "def"]);
+
+        test.input("dd");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 1 });
+
+        // text still takes enter as a newline
+        test.input("dix");
+        test.key(KeyCode::Enter);
+        test.input("y");
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string(), "abc\ndxf\n y ");
+
+        // by default, enter does nothing
+        let mut test = Test::load(&["abc", "def"]);
+        test.input("dd");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 0 });
+    }
+
+    #[test]
+    fn test_tui_paste() {
+        let paste = |config| {
+            let mut test =
+                Test::with_config(config, &["+--+", "|  |", "+--+", "abcd", "efgh", "ijkl"]);
+            for code in [
+                KeyCode::Right,
+                KeyCode::Right,
+                KeyCode::Right,
+                KeyCode::Down,
+                KeyCode::Down,
+            ] {
+                test.app
+                    .handle_key_event(KeyEvent::new(code, KeyModifiers::SHIFT))
+                    .unwrap();
+            }
+            test.input("yaaas");
+            test.app
+                .handle_key_event(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL))
+                .unwrap();
+            test.app.canvas.to_string()
+        };
+
+        assert_eq!(
+            paste(Config::default()),
+            "+--+\n|  |\n+--+\n+--+\n|  |\n+--+"
+        );
+
+        // the stencil's inside shows what was under it
+        let config = Config {
+            transparent_paste: true,
+            ..Default::default()
+        };
+        assert_eq!(paste(config), "+--+\n|  |\n+--+\n+--+\n|fg|\n+--+");
+    }
+
+    #[test]
+    fn test_headless_app() {
+        let mut app = HeadlessApp::new(Config::default(), None).unwrap();
+        for c in "ihi".chars() {
+            app.key(KeyCode::Char(c).into()).unwrap();
+        }
+        app.key(KeyCode::Esc.into()).unwrap();
+        assert_eq!(app.cursor(), UVec { x: 2, y: 0 });
+        let screen = app.render_to_string(32, 8);
+        assert!(screen.lines().nth(1).unwrap().starts_with("┃hi "));
+
+        app.key(KeyCode::Char('q').into()).unwrap();
+        assert!(app.exited());
+    }
+
+    #[test]
+    fn test_tui_rebind_enter() {
+        let mut config = Config::default();
+        config.binds.0.push((
+            "enter".into(),
+            crate::config::Binding::Single(Action::CancelShape),
+        ));
+        config.binds.0.push((
+            "C-j".into(),
+            crate::config::Binding::Single(Action::TextAddLine),
+        ));
+        let mut test = Test::with_config(config, &[]);
+
+        test.input("iab");
+        test.app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL))
+            .unwrap();
+        test.input("c");
+        // enter follows the bind rather than adding a line
+        test.key(KeyCode::Enter);
+        assert!(matches!(test.app.mode, Mode::Normal));
+        assert_eq!(test.app.canvas.to_string(), "ab\nc ");
+    }
+
+    #[test]
+    fn test_tui_text_add_line() {
+        let mut test = Test::new();
+        test.input("dia│b");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 1 });
+        // an empty line has no length to move back over
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 1, y: 2 });
+        test.input("c");
+        test.key(KeyCode::Esc);
+        let rows: Vec<_> = test
+            .app
+            .canvas
+            .rows()
+            .iter()
+            .map(String::from_iter)
+            .collect();
+        assert_eq!(rows, [" a│b", "    ", " c  "]);
+    }
+
+    #[test]
+    fn test_tui_jump() {
+        let mut config = Config::default();
+        config.binds.0.push((
+            "S-d".into(),
+            crate::config::Binding::Single(Action::JumpRight),
+        ));
+        let mut test = Test::with_config(config, &["", "  +---+   ", "  |   |   ", "  +---+   "]);
+
+        test.input("s");
+        test.input("D");
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+        test.input("D");
+        assert_eq!(test.app.cursor, UVec { x: 6, y: 1 });
+        test.input("D");
+        assert_eq!(test.app.cursor, UVec { x: 9, y: 1 });
+        // stops at the edge of the canvas
+        test.input("D");
+        assert_eq!(test.app.cursor, UVec { x: 9, y: 1 });
+    }
+
+    #[test]
+    fn test_tui_center_cursor() {
+        let mut test = Test::new();
+        test.app.canvas = Canvas::new(40, 40);
+        test.app.view_size = UVec { x: 10, y: 6 };
+
+        test.app.cursor = UVec { x: 20, y: 20 };
+        test.input("z");
+        assert_eq!(test.app.viewport, UVec { x: 15, y: 17 });
+
+        // stop at the edges of the canvas
+        test.app.cursor = UVec { x: 38, y: 2 };
+        test.input("z");
+        assert_eq!(test.app.viewport, UVec { x: 30, y: 0 });
+    }
+
+    #[test]
+    fn test_tui_render_viewport() {
+        let mut test = Test::load(&["abcd", "efgh", "ijkl"]);
+        test.app.viewport = UVec { x: 1, y: 1 };
+        test.app.cursor = UVec { x: 2, y: 1 };
+        test.app.config.highlight_cursor = true;
+
+        let buf = test.render_buf();
+        let symbol = |x, y| buf[(x, y)].symbol().to_string();
+        // +1 for the border
+        assert_eq!(symbol(1, 1), "f");
+        assert_eq!(symbol(3, 1), "h");
+        assert_eq!(symbol(1, 2), "j");
+        assert_eq!(symbol(4, 1), " ");
+        assert!(buf[(2, 1)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_tui_resize() {
+        let mut test = Test::load(&["abc"]);
+        let before = test.app.canvas.to_string();
+
+        test.input("R10x4");
+        assert_eq!(test.app.status(), Some("size (WxH): 10x4".into()));
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 10, y: 4 });
+        assert!(test.app.status().is_none());
+
+        // invalid sizes are ignored
+        test.input("Rbig");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 10, y: 4 });
+
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), before);
+    }
+
+    #[test]
+    fn test_tui_goto_coordinate() {
+        let mut test = Test::load(&["abcdefgh", "", "", "", "", ""]);
+
+        test.input("g5,3");
+        assert_eq!(test.app.status(), Some("go to (x,y): 5,3".into()));
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 5, y: 3 });
+
+        // clamped to the canvas
+        test.input("g20,1");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 7, y: 1 });
+
+        // invalid input leaves the cursor
+        test.input("gnear");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.cursor, UVec { x: 7, y: 1 });
+    }
+
+    #[test]
+    fn test_tui_draw_table() {
+        let mut test = Test::load(&["abc"]);
+
+        test.input("sdT2x3 1x1");
+        assert_eq!(test.app.status(), Some("table (RxC WxH): 2x3 1x1".into()));
+        test.key(KeyCode::Enter);
+        assert_eq!(
+            test.app.canvas.to_string(),
+            ["abc     ", " +-+-+-+", " | | | |", " +-+-+-+", " | | | |", " +-+-+-+"].join("\n")
+        );
+
+        // invalid sizes are ignored
+        test.input("T2x3");
+        test.key(KeyCode::Enter);
+
+        // as are sizes that do not fit, with a message
+        test.input("T2x3 40000x1");
+        test.key(KeyCode::Enter);
+        assert_eq!(
+            test.app.status(),
+            Some("table 2x3 40000x1 is too big".into())
+        );
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), "abc");
+    }
+
+    #[test]
+    fn test_tui_title_rect() {
+        let mut test = Test::load(&[]);
+
+        test.input("rdddddss");
+        test.app.apply_action(Action::TitleRect).unwrap();
+        test.input("Hi");
+        assert_eq!(test.app.status(), Some("title: Hi".into()));
+        test.key(KeyCode::Enter);
+        assert!(matches!(test.app.mode, Mode::Normal));
+        assert_eq!(
+            test.app.canvas.to_string(),
+            ["+-----+", "|Hi   |", "|     |", "+-----+"].join("\n")
+        );
+
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), "");
+    }
+
+    #[test]
+    fn test_tui_cycle_label() {
+        let mut test = Test::load(&["+-----+", "| foo |--> bar", "+-----+"]);
+
+        test.key(KeyCode::Tab);
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+        test.key(KeyCode::Tab);
+        assert_eq!(test.app.cursor, UVec { x: 11, y: 1 });
+        test.key(KeyCode::Tab);
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+
+        test.key(KeyCode::BackTab);
+        assert_eq!(test.app.cursor, UVec { x: 11, y: 1 });
+        test.key(KeyCode::BackTab);
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+    }
+
+    #[test]
+    fn test_tui_erase_rect() {
+        let mut test = Test::load(&["+--+ ab", "|  | cd", "+--+ ef"]);
+
+        test.app.apply_action(Action::EraseRect).unwrap();
+        assert!(matches!(test.app.mode, Mode::Erase { .. }));
+        test.input("dddddds");
+        // nothing is cleared until confirmed
+        assert_eq!(test.app.canvas.to_string(), "+--+ ab\n|  | cd\n+--+ ef");
+        test.key(KeyCode::Enter);
+        assert!(matches!(test.app.mode, Mode::Normal));
+        assert_eq!(test.app.canvas.to_string(), "       \n       \n+--+ ef");
+
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), "+--+ ab\n|  | cd\n+--+ ef");
+
+        // cancelling clears nothing
+        test.app.apply_action(Action::EraseRect).unwrap();
+        test.input("dd");
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.to_string(), "+--+ ab\n|  | cd\n+--+ ef");
+    }
+
+    #[test]
+    fn test_tui_select_fallback() {
+        let mut test = Test::load(&["abc", "def"]);
+        test.input("dm");
+        assert!(matches!(test.app.mode, Mode::Normal));
+
+        let config = Config {
+            select_fallback: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["abc", "def"]);
+        test.input("dm");
+        assert!(matches!(
+            test.app.mode,
+            Mode::Select {
+                anchor: UVec { x: 1, y: 0 }
+            }
+        ));
+
+        // the selection grows with the cursor, like any other
+        test.input("ds");
+        test.app.apply_action(Action::Delete).unwrap();
+        assert_eq!(test.app.canvas.to_string(), "a  \nd  ");
+    }
+
+    #[test]
+    fn test_tui_raise_selection() {
+        // the second box was drawn last, so its border crosses the first
+        let lines = [
+            "+-----+   ",
+            "|     |   ",
+            "|  +-----+",
+            "+--|--+  |",
+            "   |     |",
+            "   +-----+",
+        ];
+        let mut test = Test::load(&lines);
+
+        test.input("sdm");
+        test.app.apply_action(Action::RaiseSelection).unwrap();
+        assert!(matches!(test.app.mode, Mode::SelectRect { .. }));
+        assert_eq!(
+            test.app.canvas.to_string(),
+            [
+                "+-----+   ",
+                "|     |   ",
+                "|  +--|--+",
+                "+-----+  |",
+                "   |     |",
+                "   +-----+",
+            ]
+            .join("\n")
+        );
+
+        test.key(KeyCode::Esc);
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), lines.join("\n"));
+    }
+
+    #[test]
+    fn test_tui_rotate_selection() {
+        let mut test = Test::load(&["+---+", "| > |", "+---+"]);
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+
+        test.input("dsm");
+        test.app.handle_key_event(ctrl_r).unwrap();
+        assert_eq!(
+            test.app.canvas.to_string(),
+            ["+-+  ", "| |  ", "|v|  ", "| |  ", "+-+  "].join("\n")
+        );
+        match &test.app.mode {
+            Mode::SelectRect { current, .. } => {
+                assert_eq!(current.top_left, UVec { x: 0, y: 0 });
+                assert_eq!(current.bottom_right, UVec { x: 2, y: 4 });
+            }
+            mode => panic!("Not selecting a rect: {mode:?}"),
+        }
+
+        test.input("u");
+        assert_eq!(test.app.canvas.to_string(), "+---+\n| > |\n+---+");
+    }
+
+    #[test]
+    fn test_tui_select_nested_rect() {
+        let mut test = Test::load(&[
+            "+----------+",
+            "| +------+ |",
+            "| | +--+ | |",
+            "| | |  | | |",
+            "| | +--+ | |",
+            "| +------+ |",
+            "+----------+",
+        ]);
+        let selected = |test: &Test| match &test.app.mode {
+            Mode::SelectRect { current, .. } => (current.top_left, current.bottom_right),
+            mode => panic!("Not selecting a rect: {mode:?}"),
+        };
+
+        test.input("dddddsss");
+        test.input("m");
+        assert_eq!(selected(&test), (UVec { x: 4, y: 2 }, UVec { x: 7, y: 4 }));
+        test.input("m");
+        assert_eq!(selected(&test), (UVec { x: 2, y: 1 }, UVec { x: 9, y: 5 }));
+        test.input("m");
+        assert_eq!(selected(&test), (UVec { x: 0, y: 0 }, UVec { x: 11, y: 6 }));
+        // there is nothing further out
+        test.input("m");
+        assert_eq!(selected(&test), (UVec { x: 0, y: 0 }, UVec { x: 11, y: 6 }));
+    }
+
+    #[test]
+    fn test_tui_select_dashed_rect() {
+        let config = Config {
+            rect_style: RectStyle::Dashed,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &[]);
+        test.input("rddds");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.rows()[0][..5], ['┌', '┄', '┄', '┄', '┐']);
+
+        test.input("waam");
+        match &test.app.mode {
+            Mode::SelectRect { original, .. } => {
+                assert_eq!(original.top_left, UVec { x: 0, y: 0 });
+                assert_eq!(original.bottom_right, UVec { x: 4, y: 2 });
+                assert_eq!(original.glyphs, RectGlyphs::DASHED);
+            }
+            mode => panic!("Not selecting a rect: {mode:?}"),
+        }
+    }
+
+    #[test]
+    fn test_tui_expand_up_left() {
+        let config = || Config {
+            expand_up_left: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config(), &["   ab"]);
+
+        // the rect being drawn stays anchored to the content
+        test.input("rdwww");
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 0 });
+        test.key(KeyCode::Enter);
+        assert_eq!(
+            test.app.canvas.to_string(),
+            ["+-+  ", "| |  ", "+-+ab"].join("\n")
+        );
+
+        let mut test = Test::with_config(config(), &["ab"]);
+        test.input("a");
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 0 });
+        assert_eq!(test.app.canvas.to_string(), " ab");
+    }
+
+    #[test]
+    fn test_extend_selection() {
+        let mut test = Test::load(&["abcd", "efgh", "ijkl"]);
+        let shift = |code| KeyEvent::new(code, KeyModifiers::SHIFT);
+
+        for code in [KeyCode::Right, KeyCode::Right, KeyCode::Down] {
+            test.app.handle_key_event(shift(code)).unwrap();
+        }
+        assert_eq!(test.app.cursor, UVec { x: 2, y: 1 });
+
+        test.input("x");
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(rows, vec!["   d", "   h", "ijkl"]);
+
+        // the selection is gone after deleting
+        test.input("x");
+        let actual = test.app.canvas.to_string();
+        let rows: Vec<_> = actual.lines().map(|l| l.trim_end()).collect();
+        assert_eq!(rows, vec!["   d", "   h", "ijkl"]);
+    }
+
+    #[test]
+    fn test_erase_char() {
+        let config = Config {
+            erase_char: '.',
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &["abcd", "efgh", "ijkl"]);
+        let shift = |code| KeyEvent::new(code, KeyModifiers::SHIFT);
+
+        test.input("x");
+        assert_eq!(test.app.canvas.to_string(), ".bcd\nefgh\nijkl");
+
+        test.input("s");
+        for code in [KeyCode::Right, KeyCode::Right, KeyCode::Down] {
+            test.app.handle_key_event(shift(code)).unwrap();
+        }
+        test.input("x");
+        assert_eq!(test.app.canvas.to_string(), ".bcd\n...h\n...l");
+    }
+
     #[test]
     fn test_delete_rect() {
         let mut test = Test::load(&[
@@ -607,4 +3754,136 @@ mod tests {
 
         assert_snapshot!(test.render());
     }
+
+    #[test]
+    fn test_tui_load_ansi() {
+        let mut test = Test::load(&["a \x1b[31mred\x1b[0m b"]);
+        assert_eq!(test.app.canvas.to_string(), "a red b");
+        assert_eq!(
+            test.app.canvas.color(UVec { x: 2, y: 0 }),
+            Some(ansi::Color::Indexed(1))
+        );
+        assert_eq!(test.app.canvas.color(UVec { x: 1, y: 0 }), None);
+
+        let buf = test.render_buf();
+        assert_eq!(buf[(3, 1)].fg, Color::Indexed(1));
+        assert_eq!(buf[(2, 1)].fg, Color::Reset);
+
+        // drawing over a cell clears its color
+        test.input("ddix");
+        test.key(KeyCode::Esc);
+        assert_eq!(test.app.canvas.color(UVec { x: 2, y: 0 }), None);
+        assert_eq!(
+            test.app.canvas.color(UVec { x: 3, y: 0 }),
+            Some(ansi::Color::Indexed(1))
+        );
+
+        // undo puts the color back, and saving keeps it
+        test.input("u");
+        assert_eq!(
+            test.app.canvas.color(UVec { x: 2, y: 0 }),
+            Some(ansi::Color::Indexed(1))
+        );
+        test.app
+            .handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL))
+            .unwrap();
+        let actual = std::fs::read_to_string(test.tmp.path()).unwrap();
+        assert_eq!(actual, "a \x1b[31mred\x1b[0m b");
+    }
+
+    #[test]
+    fn test_tui_trim_on_load() {
+        let lines = ["ab    ", " c  ", "      ", ""];
+        let test = Test::load(&lines);
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 6, y: 3 });
+
+        let config = Config {
+            trim_on_load: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &lines);
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 2, y: 2 });
+        assert_eq!(test.app.canvas.to_string(), "ab\n c");
+
+        // the trim is not an edit
+        test.key(KeyCode::Char('u'));
+        assert_eq!(test.app.canvas.dimensions(), UVec { x: 2, y: 2 });
+    }
+
+    #[test]
+    fn test_tui_smart_home() {
+        let mut test = Test::load(&["", "   foo"]);
+        test.input("sdddddd");
+
+        // the first press stops at the first non-blank cell, the second at 0
+        test.key(KeyCode::Home);
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 1 });
+        test.key(KeyCode::Home);
+        assert_eq!(test.app.cursor, UVec { x: 0, y: 1 });
+        test.key(KeyCode::Home);
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 1 });
+
+        // another action in between starts over
+        test.input("d");
+        test.key(KeyCode::Home);
+        test.input("a");
+        test.key(KeyCode::Home);
+        assert_eq!(test.app.cursor, UVec { x: 3, y: 1 });
+    }
+
+    #[test]
+    fn test_tui_minimap() {
+        let mut lines = vec!["+----+", "|box |", "+----+"];
+        let end = format!("{:80}end", "");
+        lines.extend([""; 17]);
+        lines.extend([end.as_str(), "", "", "z"]);
+        let config = Config {
+            minimap: true,
+            ..Default::default()
+        };
+        let mut test = Test::with_config(config, &lines);
+        let buf = test.render_buf();
+        assert_snapshot!(buf_string(&buf));
+        // the viewport covers the first four columns of the top row
+        assert!(buf[(21, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(buf[(24, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(!buf[(25, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(!buf[(21, 2)].modifier.contains(Modifier::REVERSED));
+
+        test.app.apply_action(Action::ToggleMinimap).unwrap();
+        assert_eq!(test.render(), Test::load(&lines).render());
+    }
+
+    #[test]
+    fn test_tui_show_whitespace() {
+        let config = Config {
+            show_whitespace: true,
+            ..Default::default()
+        };
+        let test = Test::with_config(config, &["+--+ a", "|  |", "+--+"]);
+        assert_snapshot!(test.render());
+        assert_eq!(test.app.canvas.to_string(), "+--+ a\n|  |  \n+--+  ");
+    }
+
+    #[test]
+    fn test_tui_toggle_theme() {
+        let mut test = Test::load(&["+--+", "|  |-->", "+--+"]);
+
+        test.app.apply_action(Action::ToggleTheme).unwrap();
+        assert_eq!(test.app.config.theme.theme(), Theme::UNICODE);
+        assert_eq!(
+            test.app.canvas.to_string(),
+            ["┌──┐   ", "│  │──▶", "└──┘   "].join("\n")
+        );
+
+        // new shapes are drawn in the new theme
+        test.input("sssr");
+        test.key(KeyCode::Enter);
+        assert_eq!(test.app.canvas.rows()[3][0], '┌');
+
+        test.app.apply_action(Action::ToggleTheme).unwrap();
+        assert_eq!(test.app.config.theme.theme(), Theme::ASCII);
+        assert_eq!(test.app.canvas.rows()[0][0], '+');
+        assert_eq!(test.app.canvas.rows()[3][0], '+');
+    }
 }
@@ -1,20 +1,85 @@
-#[derive(Default, Debug, Clone, Copy)]
-#[cfg_attr(test, derive(PartialEq))]
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub struct UVec {
     pub x: u16,
     pub y: u16,
 }
 
+// Parse a position written as "x,y".
+impl std::str::FromStr for UVec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s
+            .split_once(',')
+            .with_context(|| format!("Expected x,y: {s}"))?;
+        Ok(UVec {
+            x: x.trim().parse()?,
+            y: y.trim().parse()?,
+        })
+    }
+}
+
 impl UVec {
     // Stops at 0.
     pub fn translated(&self, d: IVec) -> Self {
+        *self + d
+    }
+
+    // Distance moving only along the axes.
+    pub fn manhattan(&self, other: UVec) -> u16 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    // Distance allowing diagonal moves.
+    pub fn chebyshev(&self, other: UVec) -> u16 {
+        std::cmp::max(self.x.abs_diff(other.x), self.y.abs_diff(other.y))
+    }
+
+    // Wraps around to the opposite edge of an area of the given size.
+    pub fn wrapped(&self, d: IVec, size: UVec) -> Self {
+        let wrap = |pos: u16, d: i16, size: u16| {
+            if size == 0 {
+                pos
+            } else {
+                (pos as i32 + d as i32).rem_euclid(size as i32) as u16
+            }
+        };
         Self {
+            x: wrap(self.x, d.x, size.x),
+            y: wrap(self.y, d.y, size.y),
+        }
+    }
+}
+
+// Stops at 0.
+impl std::ops::Add<IVec> for UVec {
+    type Output = UVec;
+
+    fn add(self, d: IVec) -> UVec {
+        UVec {
             x: self.x.saturating_add_signed(d.x),
             y: self.y.saturating_add_signed(d.y),
         }
     }
 }
 
+// The delta from other to self, stopping at the bounds of i16.
+impl std::ops::Sub for UVec {
+    type Output = IVec;
+
+    fn sub(self, other: UVec) -> IVec {
+        let d =
+            |a: u16, b: u16| (a as i32 - b as i32).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        IVec {
+            x: d(self.x, other.x),
+            y: d(self.y, other.y),
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct IVec {
@@ -29,11 +94,48 @@ impl IVec {
     pub const RIGHT: IVec = IVec { x: 1, y: 0 };
 }
 
+impl std::ops::Neg for IVec {
+    type Output = IVec;
+
+    fn neg(self) -> IVec {
+        IVec {
+            x: self.x.saturating_neg(),
+            y: self.y.saturating_neg(),
+        }
+    }
+}
+
 #[cfg(test)]
 
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_uvec_ops() {
+        let a = UVec { x: 2, y: 3 };
+        let b = UVec { x: 7, y: 1 };
+        assert_eq!(a + IVec { x: 1, y: -1 }, UVec { x: 3, y: 2 });
+        assert_eq!(a + IVec { x: -5, y: -10 }, UVec { x: 0, y: 0 });
+        assert_eq!(b - a, IVec { x: 5, y: -2 });
+        assert_eq!(a - b, IVec { x: -5, y: 2 });
+        assert_eq!(a + (b - a), b);
+        assert_eq!(-(b - a), a - b);
+
+        // deltas too large for i16 stop at its bounds
+        let far = UVec { x: u16::MAX, y: 0 };
+        assert_eq!(far - UVec::default(), IVec { x: i16::MAX, y: 0 });
+        assert_eq!(UVec::default() - far, IVec { x: i16::MIN, y: 0 });
+        assert_eq!(-IVec { x: i16::MIN, y: 0 }, IVec { x: i16::MAX, y: 0 });
+    }
+
+    #[test]
+    fn test_uvec_from_str() {
+        assert_eq!("3,4".parse::<UVec>().unwrap(), UVec { x: 3, y: 4 });
+        assert_eq!(" 3, 4".parse::<UVec>().unwrap(), UVec { x: 3, y: 4 });
+        assert!("3".parse::<UVec>().is_err());
+        assert!("3,-4".parse::<UVec>().is_err());
+    }
+
     #[test]
     fn test_uvec_translate() {
         let p = UVec { x: 2, y: 3 };
@@ -44,4 +146,27 @@ mod tests {
         assert_eq!(p.translated(IVec { x: 0, y: -1 }), UVec { x: 2, y: 2 });
         assert_eq!(p.translated(IVec { x: -5, y: -10 }), UVec { x: 0, y: 0 });
     }
+
+    #[test]
+    fn test_uvec_distance() {
+        let a = UVec { x: 2, y: 3 };
+        let b = UVec { x: 7, y: 1 };
+        assert_eq!(a.manhattan(b), 7);
+        assert_eq!(b.manhattan(a), 7);
+        assert_eq!(a.chebyshev(b), 5);
+        assert_eq!(b.chebyshev(a), 5);
+        assert_eq!(a.manhattan(a), 0);
+        assert_eq!(a.chebyshev(a), 0);
+    }
+
+    #[test]
+    fn test_uvec_wrapped() {
+        let p = UVec { x: 0, y: 3 };
+        let size = UVec { x: 8, y: 4 };
+        assert_eq!(p.wrapped(IVec { x: 1, y: 0 }, size), UVec { x: 1, y: 3 });
+        assert_eq!(p.wrapped(IVec { x: -1, y: 0 }, size), UVec { x: 7, y: 3 });
+        assert_eq!(p.wrapped(IVec { x: 0, y: 1 }, size), UVec { x: 0, y: 0 });
+        assert_eq!(p.wrapped(IVec { x: -9, y: -4 }, size), UVec { x: 7, y: 3 });
+        assert_eq!(p.wrapped(IVec { x: -1, y: 1 }, UVec::default()), p);
+    }
 }